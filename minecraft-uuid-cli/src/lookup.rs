@@ -0,0 +1,78 @@
+use anyhow::Result;
+use minecraft_uuid::{get_username_by_uuid, get_uuid_by_username, offline_uuid, MinecraftUuid};
+use serde::Serialize;
+
+/// The result of looking up a single username or UUID.
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct Record {
+    pub input: String,
+    pub name: Option<String>,
+    pub uuid: Option<String>,
+    pub uuid_hyphenated: Option<String>,
+    pub error: Option<String>,
+}
+
+impl Record {
+    fn ok(input: &str, name: String, uuid: MinecraftUuid) -> Self {
+        Self {
+            input: input.to_owned(),
+            name: Some(name),
+            uuid: Some(uuid.simple()),
+            uuid_hyphenated: Some(uuid.hyphenated()),
+            error: None,
+        }
+    }
+
+    fn err(input: &str, err: impl ToString) -> Self {
+        Self {
+            input: input.to_owned(),
+            error: Some(err.to_string()),
+            ..Default::default()
+        }
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Resolves the given username to its UUID or the given
+/// UUID to its username, depending on what has been passed.
+pub async fn lookup(uuid_or_name: &str) -> Record {
+    if let Ok(uuid) = uuid_or_name.parse::<MinecraftUuid>() {
+        return match get_username_by_uuid(&uuid.simple()).await {
+            Ok(name) => Record::ok(uuid_or_name, name, uuid),
+            Err(err) => Record::err(uuid_or_name, err),
+        };
+    }
+
+    // The name is looked up again to report it with its actual
+    // capitalization instead of echoing the input.
+    let res = async {
+        let uuid = resolve_uuid(uuid_or_name).await?;
+        let name = get_username_by_uuid(&uuid.simple()).await?;
+        anyhow::Ok((name, uuid))
+    };
+
+    match res.await {
+        Ok((name, uuid)) => Record::ok(uuid_or_name, name, uuid),
+        Err(err) => Record::err(uuid_or_name, err),
+    }
+}
+
+/// Computes the offline-mode UUID of the given username
+/// without any network requests.
+pub fn offline(name: &str) -> Record {
+    Record::ok(name, name.to_owned(), offline_uuid(name))
+}
+
+/// Returns the given value if it is a UUID or
+/// otherwise looks up the UUID of the given username.
+pub async fn resolve_uuid(uuid_or_name: &str) -> Result<MinecraftUuid> {
+    if let Ok(uuid) = uuid_or_name.parse() {
+        return Ok(uuid);
+    }
+
+    let uuid = get_uuid_by_username(uuid_or_name).await?;
+    Ok(uuid.parse()?)
+}
@@ -1,31 +1,215 @@
-use minecraft_uuid::{get_username_by_uuid, get_uuid_by_username};
-use std::env;
-use uuid::Uuid;
+mod lookup;
+mod output;
+
+use anyhow::Result;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap_complete::Shell;
+use minecraft_uuid::{get_profile, get_skin};
+use output::Format;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Convert Minecraft usernames to UUIDs and vice versa.
+///
+/// Without a subcommand, the arguments are looked up like with
+/// `lookup`.
+#[derive(Parser)]
+#[command(version, args_conflicts_with_subcommands = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    lookup: LookupArgs,
+}
+
+#[derive(Args)]
+struct LookupArgs {
+    /// Usernames or UUIDs to look up.
+    inputs: Vec<String>,
+
+    /// Read newline-separated usernames or UUIDs from a file.
+    #[arg(long, value_name = "PATH")]
+    file: Option<PathBuf>,
+
+    /// The output format.
+    #[arg(short, long, value_enum, default_value_t)]
+    format: Format,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Look up the UUIDs of usernames or the usernames of UUIDs.
+    ///
+    /// Names and UUIDs are read from the arguments, from the given
+    /// file or, if neither is given, newline-separated from stdin.
+    /// Pass `-` to explicitly read from stdin.
+    ///
+    /// Exits with 0 if all lookups succeeded, 2 if some failed and
+    /// 1 if all failed.
+    Lookup(LookupArgs),
+
+    /// Print the full profile of a player, including skin and cape.
+    Profile {
+        /// The username or UUID of the player.
+        name_or_uuid: String,
+
+        /// The output format.
+        #[arg(short, long, value_enum, default_value_t)]
+        format: Format,
+    },
+
+    /// Download the skin PNG of a player.
+    Skin {
+        /// The username or UUID of the player.
+        name_or_uuid: String,
+
+        /// Where to write the PNG to. Defaults to `<name_or_uuid>.png`.
+        #[arg(short, long, value_name = "PATH")]
+        output: Option<PathBuf>,
+    },
+
+    /// Compute offline-mode UUIDs locally, without any network requests.
+    Offline {
+        /// Usernames to compute the UUIDs for.
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// The output format.
+        #[arg(short, long, value_enum, default_value_t)]
+        format: Format,
+    },
+
+    /// Print shell completions to stdout.
+    Completions {
+        /// The shell to generate the completions for.
+        shell: Shell,
+    },
+}
 
 #[tokio::main]
-async fn main() -> Result<(), String> {
-    let args: Vec<String> = env::args().collect();
-    let uuid_or_name = args.get(1);
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let command = cli.command.unwrap_or(Command::Lookup(cli.lookup));
+
+    let res = match command {
+        Command::Lookup(args) => run_lookup(args).await,
+        Command::Profile {
+            name_or_uuid,
+            format,
+        } => run_profile(&name_or_uuid, format).await,
+        Command::Skin {
+            name_or_uuid,
+            output,
+        } => run_skin(&name_or_uuid, output).await,
+        Command::Offline { names, format } => {
+            let records: Vec<_> = names.iter().map(|n| lookup::offline(n)).collect();
+            output::write(io::stdout().lock(), format, &records)
+                .map(|_| ExitCode::SUCCESS)
+                .map_err(Into::into)
+        }
+        Command::Completions { shell } => {
+            let mut cmd = Cli::command();
+            let name = cmd.get_name().to_owned();
+            clap_complete::generate(shell, &mut cmd, name, &mut io::stdout());
+            Ok(ExitCode::SUCCESS)
+        }
+    };
+
+    res.unwrap_or_else(|err| {
+        eprintln!("{err}");
+        ExitCode::FAILURE
+    })
+}
 
-    if uuid_or_name.is_none() {
-        return Err("Please specify a Minecraft username or UUID as first argument.".into());
+async fn run_lookup(args: LookupArgs) -> Result<ExitCode> {
+    let LookupArgs {
+        mut inputs,
+        file,
+        format,
+    } = args;
+    let read_stdin = inputs.iter().any(|i| i == "-");
+    inputs.retain(|i| i != "-");
+
+    if let Some(path) = file {
+        let content = fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed reading {}: {e}", path.display()))?;
+        inputs.extend(split_lines(&content));
+    }
+
+    if read_stdin || (inputs.is_empty() && !io::stdin().is_terminal()) {
+        let mut content = String::new();
+        io::stdin()
+            .read_to_string(&mut content)
+            .map_err(|e| anyhow::anyhow!("Failed reading stdin: {e}"))?;
+        inputs.extend(split_lines(&content));
+    }
+
+    if inputs.is_empty() {
+        anyhow::bail!("Please specify at least one Minecraft username or UUID.");
     }
 
-    let uuid_or_name = uuid_or_name.unwrap();
+    let mut records = Vec::with_capacity(inputs.len());
+    for input in &inputs {
+        records.push(lookup::lookup(input).await);
+    }
+
+    output::write(io::stdout().lock(), format, &records)?;
+
+    Ok(match records.iter().filter(|r| r.is_ok()).count() {
+        n if n == records.len() => ExitCode::SUCCESS,
+        0 => ExitCode::FAILURE,
+        _ => ExitCode::from(2),
+    })
+}
+
+async fn run_profile(name_or_uuid: &str, format: Format) -> Result<ExitCode> {
+    let uuid = lookup::resolve_uuid(name_or_uuid).await?;
+    let profile = get_profile(&uuid).await?;
+    output::write_profile(io::stdout().lock(), format, &profile)?;
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn run_skin(name_or_uuid: &str, output: Option<PathBuf>) -> Result<ExitCode> {
+    let uuid = lookup::resolve_uuid(name_or_uuid).await?;
+    let png = get_skin(&uuid).await?;
+
+    let path = output.unwrap_or_else(|| format!("{name_or_uuid}.png").into());
+    fs::write(&path, png).map_err(|e| anyhow::anyhow!("Failed writing {}: {e}", path.display()))?;
+    eprintln!("Skin written to {}", path.display());
+
+    Ok(ExitCode::SUCCESS)
+}
 
-    let uuid = Uuid::parse_str(uuid_or_name).ok();
+fn split_lines(content: &str) -> impl Iterator<Item = String> + '_ {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    if let Some(uuid) = uuid {
-        let res = get_username_by_uuid(&uuid.to_string())
-            .await
-            .map_err(|e| e.to_string())?;
-        println!("{res}");
-    } else {
-        let res = get_uuid_by_username(uuid_or_name)
-            .await
-            .map_err(|e| e.to_string())?;
-        println!("{res}");
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
     }
 
-    Ok(())
+    #[test]
+    fn test_default_lookup() {
+        let cli = Cli::try_parse_from(["minecraft-uuid-cli", "Notch", "-f", "json"]).unwrap();
+        assert!(cli.command.is_none());
+        assert_eq!(cli.lookup.inputs, ["Notch"]);
+
+        let cli = Cli::try_parse_from(["minecraft-uuid-cli", "lookup", "Notch"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Lookup(a)) if a.inputs == ["Notch"]));
+
+        let cli = Cli::try_parse_from(["minecraft-uuid-cli"]).unwrap();
+        assert!(cli.command.is_none() && cli.lookup.inputs.is_empty());
+    }
 }
@@ -0,0 +1,137 @@
+use crate::lookup::Record;
+use clap::ValueEnum;
+use minecraft_uuid::PlayerProfile;
+use std::io::{self, Write};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Only the resolved name or UUID, one per line.
+    #[default]
+    Text,
+    /// A JSON array of all results.
+    Json,
+    /// CSV with a header line.
+    Csv,
+}
+
+/// Writes the given records to `out` in the given format.
+///
+/// In text format, only the resolved counterpart of each input
+/// is printed and errors are written to stderr.
+pub fn write(mut out: impl Write, format: Format, records: &[Record]) -> io::Result<()> {
+    match format {
+        Format::Text => {
+            for r in records {
+                match (&r.error, r.input == r.name.as_deref().unwrap_or_default()) {
+                    (Some(err), _) => eprintln!("{}: {err}", r.input),
+                    (None, true) => writeln!(out, "{}", r.uuid.as_deref().unwrap_or_default())?,
+                    (None, false) => writeln!(out, "{}", r.name.as_deref().unwrap_or_default())?,
+                }
+            }
+        }
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, records)?;
+            writeln!(out)?;
+        }
+        Format::Csv => {
+            writeln!(out, "input,name,uuid,uuid_hyphenated,error")?;
+            for r in records {
+                let fields = [
+                    Some(r.input.as_str()),
+                    r.name.as_deref(),
+                    r.uuid.as_deref(),
+                    r.uuid_hyphenated.as_deref(),
+                    r.error.as_deref(),
+                ];
+                let line = fields
+                    .iter()
+                    .map(|f| csv_escape(f.unwrap_or_default()))
+                    .collect::<Vec<_>>()
+                    .join(",");
+                writeln!(out, "{line}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the given profile to `out` in the given format.
+///
+/// CSV is not supported for profiles and falls back to text.
+pub fn write_profile(
+    mut out: impl Write,
+    format: Format,
+    profile: &PlayerProfile,
+) -> io::Result<()> {
+    if format == Format::Json {
+        serde_json::to_writer_pretty(&mut out, profile)?;
+        return writeln!(out);
+    }
+
+    writeln!(out, "Name:  {}", profile.name)?;
+    writeln!(out, "UUID:  {:#}", profile.uuid)?;
+    match &profile.skin {
+        Some(skin) => {
+            let model = if skin.slim { "slim" } else { "classic" };
+            writeln!(out, "Skin:  {} ({model})", skin.url)?;
+        }
+        None => writeln!(out, "Skin:  default")?,
+    }
+    writeln!(out, "Cape:  {}", profile.cape.as_deref().unwrap_or("none"))
+}
+
+fn csv_escape(v: &str) -> String {
+    if v.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", v.replace('"', "\"\""))
+    } else {
+        v.to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                input: "zekrotja".into(),
+                name: Some("zekrotja".into()),
+                uuid: Some("c3371e36f2884eaeb9d5b90e47258444".into()),
+                uuid_hyphenated: Some("c3371e36-f288-4eae-b9d5-b90e47258444".into()),
+                error: None,
+            },
+            Record {
+                input: "shit".into(),
+                error: Some("404 (NotFound): \"shit\", not found".into()),
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let mut out = Vec::new();
+        write(&mut out, Format::Csv, &records()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                "input,name,uuid,uuid_hyphenated,error\n",
+                "zekrotja,zekrotja,c3371e36f2884eaeb9d5b90e47258444,",
+                "c3371e36-f288-4eae-b9d5-b90e47258444,\n",
+                "shit,,,,\"404 (NotFound): \"\"shit\"\", not found\"\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_text() {
+        let mut out = Vec::new();
+        write(&mut out, Format::Text, &records()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "c3371e36f2884eaeb9d5b90e47258444\n"
+        );
+    }
+}
@@ -1,4 +1,8 @@
 const API_ROOT: &str = "https://api.mojang.com";
+const PLAYERDB_API_ROOT: &str = "https://playerdb.co/api";
+const ASHCON_API_ROOT: &str = "https://api.ashcon.app";
+const GEYSER_API_ROOT: &str = "https://api.geysermc.org/v2";
+const SESSION_API_ROOT: &str = "https://sessionserver.mojang.com";
 
 pub(crate) fn usernames(username: &str) -> String {
     format!("{API_ROOT}/users/profiles/minecraft/{username}")
@@ -7,3 +11,23 @@ pub(crate) fn usernames(username: &str) -> String {
 pub(crate) fn uids(uuid: &str) -> String {
     format!("{API_ROOT}/user/profile/{uuid}")
 }
+
+pub(crate) fn playerdb_player(username_or_uuid: &str) -> String {
+    format!("{PLAYERDB_API_ROOT}/player/minecraft/{username_or_uuid}")
+}
+
+pub(crate) fn ashcon_user(username_or_uuid: &str) -> String {
+    format!("{ASHCON_API_ROOT}/mojang/v2/user/{username_or_uuid}")
+}
+
+pub(crate) fn geyser_xuid(gamertag: &str) -> String {
+    format!("{GEYSER_API_ROOT}/xbox/xuid/{gamertag}")
+}
+
+pub(crate) fn geyser_gamertag(xuid: u64) -> String {
+    format!("{GEYSER_API_ROOT}/xbox/gamertag/{xuid}")
+}
+
+pub(crate) fn session_profile(uuid: &str) -> String {
+    format!("{SESSION_API_ROOT}/session/minecraft/profile/{uuid}")
+}
@@ -1,4 +1,6 @@
-use crate::models::ErrorResponse;
+use crate::models::{
+    AshconErrorResponse, ErrorResponse, GeyserErrorResponse, PlayerDbErrorResponse,
+};
 use std::error::Error;
 use std::fmt::Display;
 
@@ -62,3 +64,61 @@ impl From<ErrorResponse> for APIError {
         }
     }
 }
+
+impl From<PlayerDbErrorResponse> for APIError {
+    fn from(resp: PlayerDbErrorResponse) -> Self {
+        Self {
+            status_code: 0,
+            message: resp.message,
+            status: resp.code,
+        }
+    }
+}
+
+impl From<AshconErrorResponse> for APIError {
+    fn from(resp: AshconErrorResponse) -> Self {
+        Self {
+            status_code: 0,
+            message: resp.reason,
+            status: resp.error,
+        }
+    }
+}
+
+impl From<GeyserErrorResponse> for APIError {
+    fn from(resp: GeyserErrorResponse) -> Self {
+        Self {
+            status_code: 0,
+            message: resp.message,
+            status: String::new(),
+        }
+    }
+}
+
+/// Error returned when a string can not be parsed
+/// into a [`MinecraftUuid`](crate::MinecraftUuid).
+#[derive(Debug, Clone)]
+pub struct ParseUuidError {
+    value: String,
+}
+
+impl ParseUuidError {
+    /// The value which failed to parse.
+    pub fn value(&self) -> &str {
+        self.value.as_ref()
+    }
+
+    pub(crate) fn new(value: &str) -> Self {
+        Self {
+            value: value.to_owned(),
+        }
+    }
+}
+
+impl Error for ParseUuidError {}
+
+impl Display for ParseUuidError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "invalid Minecraft UUID: {:?}", self.value)
+    }
+}
@@ -1,12 +1,23 @@
 mod endpoints;
 pub mod error;
 mod models;
+mod profile;
+pub mod providers;
+mod uuid;
 
 use anyhow::Result;
 use error::APIError;
 use models::{ErrorResponse, UUIDResponse};
 use serde::de::DeserializeOwned;
 
+pub use profile::{get_profile, get_skin, PlayerProfile, Skin};
+pub use providers::{
+    floodgate_uuid, floodgate_xuid, is_offline_uuid, offline_uuid, AshconProvider,
+    FallbackProvider, FloodgateProvider, MojangProvider, OfflineProvider, PlayerDbProvider,
+    ProfileProvider,
+};
+pub use uuid::MinecraftUuid;
+
 /// Get a UUID from the given Minecraft username
 /// from the Mojang API.
 ///
@@ -20,7 +31,7 @@ use serde::de::DeserializeOwned;
 /// # }
 /// ```
 pub async fn get_uuid_by_username(username: &str) -> Result<String> {
-    let res: UUIDResponse = get(&endpoints::usernames(username)).await?;
+    let res: UUIDResponse = get::<_, ErrorResponse>(&endpoints::usernames(username)).await?;
     Ok(res.id)
 }
 
@@ -37,21 +48,32 @@ pub async fn get_uuid_by_username(username: &str) -> Result<String> {
 /// # }
 /// ```
 pub async fn get_username_by_uuid(uuid: &str) -> Result<String> {
-    let res: UUIDResponse = get(&endpoints::uids(uuid)).await?;
+    let res: UUIDResponse = get::<_, ErrorResponse>(&endpoints::uids(uuid)).await?;
     Ok(res.name)
 }
 
-async fn get<T: DeserializeOwned>(url: &str) -> Result<T> {
+/// Requests the given URL and decodes the response body into `T`.
+///
+/// Erroneous responses are decoded into `E` and converted into an
+/// [`APIError`]. If the error body can not be decoded, an [`APIError`]
+/// is built from the response status only.
+async fn get<T, E>(url: &str) -> Result<T>
+where
+    T: DeserializeOwned,
+    E: DeserializeOwned + Into<APIError>,
+{
     let resp = reqwest::get(url).await?;
 
-    let status = u16::from(resp.status());
-    if status == 204 {
+    let status = resp.status();
+    if status.as_u16() == 204 {
         let err = APIError::new(404, "NotFound", "This user does not exist.");
         return Err(err.into());
-    } else if status > 399 {
-        let body: ErrorResponse = resp.json().await?;
-        let mut err: APIError = body.into();
-        err.set_status_code(status);
+    } else if status.as_u16() > 399 {
+        let mut err: APIError = match resp.json::<E>().await {
+            Ok(body) => body.into(),
+            Err(_) => APIError::new(0, status.canonical_reason().unwrap_or_default(), ""),
+        };
+        err.set_status_code(status.as_u16());
         return Err(err.into());
     }
 
@@ -12,3 +12,91 @@ pub(crate) struct ErrorResponse {
     #[serde(rename = "errorMessage")]
     pub error_message: String,
 }
+
+#[derive(Deserialize)]
+pub(crate) struct PlayerDbResponse {
+    pub data: PlayerDbData,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PlayerDbData {
+    pub player: PlayerDbPlayer,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PlayerDbPlayer {
+    pub username: String,
+    pub raw_id: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct PlayerDbErrorResponse {
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AshconResponse {
+    pub uuid: String,
+    pub username: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct AshconErrorResponse {
+    pub error: String,
+    pub reason: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct GeyserXuidResponse {
+    pub xuid: u64,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct GeyserGamertagResponse {
+    pub gamertag: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct GeyserErrorResponse {
+    pub message: String,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SessionProfileResponse {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub properties: Vec<SessionProfileProperty>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct SessionProfileProperty {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Deserialize, Default)]
+pub(crate) struct TexturesPayload {
+    #[serde(default)]
+    pub textures: TexturesPayloadTextures,
+}
+
+#[derive(Deserialize, Default)]
+pub(crate) struct TexturesPayloadTextures {
+    #[serde(rename = "SKIN")]
+    pub skin: Option<TexturePayload>,
+    #[serde(rename = "CAPE")]
+    pub cape: Option<TexturePayload>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TexturePayload {
+    pub url: String,
+    pub metadata: Option<TextureMetadataPayload>,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct TextureMetadataPayload {
+    pub model: Option<String>,
+}
@@ -0,0 +1,104 @@
+use crate::models::{ErrorResponse, SessionProfileResponse, TexturePayload, TexturesPayload};
+use crate::{endpoints, get, MinecraftUuid};
+use anyhow::Result;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::Serialize;
+
+/// The full profile of a Minecraft account as
+/// provided by the Mojang session server.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PlayerProfile {
+    pub uuid: MinecraftUuid,
+    pub name: String,
+    pub skin: Option<Skin>,
+    pub cape: Option<String>,
+}
+
+/// The skin texture of a player.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Skin {
+    /// The URL of the skin PNG.
+    pub url: String,
+    /// Whether the skin uses the slim ("Alex") arm model.
+    pub slim: bool,
+}
+
+impl From<TexturePayload> for Skin {
+    fn from(t: TexturePayload) -> Self {
+        let slim = t
+            .metadata
+            .and_then(|m| m.model)
+            .is_some_and(|m| m == "slim");
+        Self { url: t.url, slim }
+    }
+}
+
+/// Get the full profile including skin and cape of the
+/// given account UUID from the Mojang session server.
+///
+/// # Example
+/// ```
+/// # use minecraft_uuid::*;
+/// # async fn run() -> anyhow::Result<()> {
+/// let uuid = "c3371e36f2884eaeb9d5b90e47258444".parse()?;
+/// let profile = get_profile(&uuid).await?;
+/// assert_eq!(profile.name, "zekroTJA");
+/// # Ok(())
+/// # }
+/// ```
+pub async fn get_profile(uuid: &MinecraftUuid) -> Result<PlayerProfile> {
+    let res: SessionProfileResponse =
+        get::<_, ErrorResponse>(&endpoints::session_profile(&uuid.simple())).await?;
+
+    let textures = match res.properties.into_iter().find(|p| p.name == "textures") {
+        Some(prop) => decode_textures(&prop.value)?,
+        None => TexturesPayload::default(),
+    };
+
+    Ok(PlayerProfile {
+        uuid: res.id.parse()?,
+        name: res.name,
+        skin: textures.textures.skin.map(Skin::from),
+        cape: textures.textures.cape.map(|c| c.url),
+    })
+}
+
+/// Downloads the skin PNG of the given account UUID.
+///
+/// Returns an error if the player has no custom skin set.
+pub async fn get_skin(uuid: &MinecraftUuid) -> Result<Vec<u8>> {
+    let profile = get_profile(uuid).await?;
+    let Some(skin) = profile.skin else {
+        anyhow::bail!("{} has no custom skin set", profile.name);
+    };
+
+    let resp = reqwest::get(&skin.url).await?.error_for_status()?;
+    Ok(resp.bytes().await?.to_vec())
+}
+
+fn decode_textures(value: &str) -> Result<TexturesPayload> {
+    let raw = STANDARD.decode(value)?;
+    Ok(serde_json::from_slice(&raw)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_textures() {
+        let payload = concat!(
+            r#"{"timestamp":1670000000000,"profileId":"c3371e36f2884eaeb9d5b90e47258444","#,
+            r#""profileName":"zekroTJA","textures":{"SKIN":{"url":"http://textures.minecraft.net/texture/abc","#,
+            r#""metadata":{"model":"slim"}}}}"#
+        );
+        let encoded = STANDARD.encode(payload);
+
+        let textures = decode_textures(&encoded).unwrap().textures;
+        let skin = Skin::from(textures.skin.unwrap());
+        assert_eq!(skin.url, "http://textures.minecraft.net/texture/abc");
+        assert!(skin.slim);
+        assert!(textures.cape.is_none());
+    }
+}
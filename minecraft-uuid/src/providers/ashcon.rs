@@ -0,0 +1,33 @@
+use super::ProfileProvider;
+use crate::models::{AshconErrorResponse, AshconResponse};
+use crate::{endpoints, get, MinecraftUuid};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Resolves profiles using the [Ashcon](https://github.com/Electroid/mojang-api)
+/// mirror API.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AshconProvider;
+
+impl AshconProvider {
+    async fn lookup(&self, username_or_uuid: &str) -> Result<AshconResponse> {
+        get::<_, AshconErrorResponse>(&endpoints::ashcon_user(username_or_uuid)).await
+    }
+}
+
+#[async_trait]
+impl ProfileProvider for AshconProvider {
+    fn name(&self) -> &str {
+        "ashcon"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        let res = self.lookup(username).await?;
+        Ok(res.uuid.parse()?)
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        let res = self.lookup(&uuid.simple()).await?;
+        Ok(res.username)
+    }
+}
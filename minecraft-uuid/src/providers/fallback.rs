@@ -0,0 +1,150 @@
+use super::ProfileProvider;
+use crate::error::APIError;
+use crate::MinecraftUuid;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::future::Future;
+
+/// Tries a list of providers in order until one of
+/// them is able to answer the request.
+///
+/// The next provider is only tried when the current one
+/// appears to be unavailable, which means the request failed
+/// on the transport level or the API responded with a `429`
+/// or `5xx` status. Definitive answers like "this user does
+/// not exist" are returned right away.
+///
+/// # Example
+/// ```
+/// # use minecraft_uuid::*;
+/// # async fn run() -> anyhow::Result<()> {
+/// let provider = FallbackProvider::default()
+///     .with(MojangProvider)
+///     .with(PlayerDbProvider)
+///     .with(AshconProvider);
+///
+/// let uuid = provider.get_uuid_by_username("zekrotja").await?;
+/// assert_eq!(uuid.to_string(), "c3371e36f2884eaeb9d5b90e47258444");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct FallbackProvider {
+    providers: Vec<Box<dyn ProfileProvider>>,
+}
+
+impl FallbackProvider {
+    pub fn new(providers: Vec<Box<dyn ProfileProvider>>) -> Self {
+        Self { providers }
+    }
+
+    /// Appends the given provider to the end of the chain.
+    pub fn with(mut self, provider: impl ProfileProvider + 'static) -> Self {
+        self.providers.push(Box::new(provider));
+        self
+    }
+
+    async fn try_all<'a, T, F, Fut>(&'a self, f: F) -> Result<T>
+    where
+        F: Fn(&'a dyn ProfileProvider) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut last_err = None;
+
+        for provider in &self.providers {
+            match f(provider.as_ref()).await {
+                Ok(v) => return Ok(v),
+                Err(err) if is_unavailable(&err) => {
+                    last_err = Some(err.context(format!("provider {}", provider.name())))
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| anyhow::anyhow!("No profile providers configured")))
+    }
+}
+
+fn is_unavailable(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<APIError>() {
+        Some(err) => err.status_code() == 429 || err.status_code() >= 500,
+        None => true,
+    }
+}
+
+#[async_trait]
+impl ProfileProvider for FallbackProvider {
+    fn name(&self) -> &str {
+        "fallback"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        self.try_all(|p| p.get_uuid_by_username(username)).await
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        self.try_all(|p| p.get_username_by_uuid(uuid)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct StaticProvider(u16);
+
+    impl StaticProvider {
+        fn result(&self) -> Result<u16> {
+            match self.0 {
+                200 => Ok(self.0),
+                0 => Err(anyhow::anyhow!("connection refused")),
+                code => Err(APIError::new(code, "Error", "").into()),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl ProfileProvider for StaticProvider {
+        fn name(&self) -> &str {
+            "static"
+        }
+
+        async fn get_uuid_by_username(&self, _username: &str) -> Result<MinecraftUuid> {
+            self.result().map(|v| MinecraftUuid::from_u128(v.into()))
+        }
+
+        async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+            self.result().map(|v| format!("{}-{v}", uuid.as_u128()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback() {
+        let p = FallbackProvider::default()
+            .with(StaticProvider(0))
+            .with(StaticProvider(503))
+            .with(StaticProvider(429))
+            .with(StaticProvider(200));
+        let uuid = p.get_uuid_by_username("a").await.unwrap();
+        assert_eq!(uuid, MinecraftUuid::from_u128(200));
+        assert_eq!(p.get_username_by_uuid(&uuid).await.unwrap(), "200-200");
+
+        let p = FallbackProvider::default()
+            .with(StaticProvider(0))
+            .with(StaticProvider(404))
+            .with(StaticProvider(200));
+        let err = p.get_uuid_by_username("a").await.unwrap_err();
+        let err = err.downcast_ref::<APIError>().unwrap();
+        assert_eq!(err.status_code(), 404);
+
+        let p = FallbackProvider::default()
+            .with(StaticProvider(0))
+            .with(StaticProvider(500));
+        let err = p.get_uuid_by_username("a").await.unwrap_err();
+        let err = err.downcast_ref::<APIError>().unwrap();
+        assert_eq!(err.status_code(), 500);
+
+        let p = FallbackProvider::default();
+        assert!(p.get_uuid_by_username("a").await.is_err());
+    }
+}
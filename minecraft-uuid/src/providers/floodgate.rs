@@ -0,0 +1,136 @@
+use super::ProfileProvider;
+use crate::error::APIError;
+use crate::models::{GeyserErrorResponse, GeyserGamertagResponse, GeyserXuidResponse};
+use crate::{endpoints, get, MinecraftUuid};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// The username prefix Floodgate uses by default.
+pub const DEFAULT_FLOODGATE_PREFIX: &str = ".";
+
+/// Generates the UUID Floodgate assigns to the Bedrock
+/// player with the given Xbox user ID (XUID).
+///
+/// # Example
+/// ```
+/// # use minecraft_uuid::*;
+/// let uuid = floodgate_uuid(2535428419547418);
+/// assert_eq!(uuid.to_string(), "0000000000000000000901f56e4cf11a");
+/// ```
+pub fn floodgate_uuid(xuid: u64) -> MinecraftUuid {
+    MinecraftUuid::from_u128(xuid.into())
+}
+
+/// Extracts the Xbox user ID (XUID) from the given
+/// Floodgate UUID.
+///
+/// Returns `None` if the UUID has not been generated by Floodgate.
+///
+/// # Example
+/// ```
+/// # use minecraft_uuid::*;
+/// let uuid = "00000000-0000-0000-0009-01f56e4cf11a".parse().unwrap();
+/// assert_eq!(floodgate_xuid(&uuid), Some(2535428419547418));
+/// ```
+pub fn floodgate_xuid(uuid: &MinecraftUuid) -> Option<u64> {
+    let v = uuid.as_u128();
+    if v >> 64 != 0 {
+        return None;
+    }
+    Some(v as u64)
+}
+
+/// Resolves Bedrock players joining through
+/// [Geyser](https://geysermc.org) and Floodgate.
+///
+/// Usernames must carry the configured Floodgate prefix
+/// (`.` by default). Gamertags are resolved to XUIDs using
+/// the GeyserMC global API.
+#[derive(Debug, Clone)]
+pub struct FloodgateProvider {
+    prefix: String,
+}
+
+impl Default for FloodgateProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_FLOODGATE_PREFIX)
+    }
+}
+
+impl FloodgateProvider {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+
+    /// The username prefix used to tell Bedrock players apart.
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Returns the Xbox gamertag of the given username if it
+    /// carries the Floodgate prefix.
+    pub fn strip_prefix<'a>(&self, username: &'a str) -> Option<&'a str> {
+        username
+            .strip_prefix(&self.prefix)
+            .filter(|gamertag| !gamertag.is_empty())
+    }
+}
+
+#[async_trait]
+impl ProfileProvider for FloodgateProvider {
+    fn name(&self) -> &str {
+        "floodgate"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        let gamertag = self.strip_prefix(username).ok_or_else(|| {
+            APIError::new(
+                400,
+                "BadRequest",
+                &format!("Bedrock usernames must start with '{}'.", self.prefix),
+            )
+        })?;
+
+        let res: GeyserXuidResponse =
+            get::<_, GeyserErrorResponse>(&endpoints::geyser_xuid(gamertag)).await?;
+        Ok(floodgate_uuid(res.xuid))
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        let xuid = floodgate_xuid(uuid)
+            .ok_or_else(|| APIError::new(400, "BadRequest", "This is not a Floodgate UUID."))?;
+
+        let res: GeyserGamertagResponse =
+            get::<_, GeyserErrorResponse>(&endpoints::geyser_gamertag(xuid)).await?;
+        Ok(format!("{}{}", self.prefix, res.gamertag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_floodgate_uuid() {
+        let uuid = floodgate_uuid(2535428419547418);
+        assert_eq!(uuid.to_string(), "0000000000000000000901f56e4cf11a");
+        assert_eq!(floodgate_xuid(&uuid), Some(2535428419547418));
+
+        let uuid = "c3371e36f2884eaeb9d5b90e47258444".parse().unwrap();
+        assert_eq!(floodgate_xuid(&uuid), None);
+    }
+
+    #[test]
+    fn test_strip_prefix() {
+        let p = FloodgateProvider::default();
+        assert_eq!(p.strip_prefix(".Steve"), Some("Steve"));
+        assert_eq!(p.strip_prefix("Steve"), None);
+        assert_eq!(p.strip_prefix("."), None);
+
+        let p = FloodgateProvider::new("*");
+        assert_eq!(p.strip_prefix("*Steve"), Some("Steve"));
+        assert_eq!(p.strip_prefix(".Steve"), None);
+    }
+}
@@ -0,0 +1,75 @@
+mod ashcon;
+mod fallback;
+mod floodgate;
+mod mojang;
+mod offline;
+mod playerdb;
+
+pub use ashcon::*;
+pub use fallback::*;
+pub use floodgate::*;
+pub use mojang::*;
+pub use offline::*;
+pub use playerdb::*;
+
+use crate::MinecraftUuid;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// A source which is able to resolve Minecraft usernames
+/// to account UUIDs and vice versa.
+#[async_trait]
+pub trait ProfileProvider: Send + Sync {
+    /// A short, human readable name of the provider
+    /// used in logs and error messages.
+    fn name(&self) -> &str;
+
+    /// Get the UUID of the account with the given username.
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid>;
+
+    /// Get the current username of the account with the
+    /// given UUID.
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String>;
+
+    /// Get the current usernames of all accounts with the
+    /// given UUIDs.
+    ///
+    /// As there is no bulk endpoint for UUIDs, they are looked
+    /// up one after another. Each UUID gets its own result, so
+    /// that a failed lookup does not affect the others.
+    async fn get_usernames_by_uuids(
+        &self,
+        uuids: &[MinecraftUuid],
+    ) -> HashMap<MinecraftUuid, Result<String>> {
+        let mut res = HashMap::with_capacity(uuids.len());
+
+        for uuid in uuids {
+            res.insert(*uuid, self.get_username_by_uuid(uuid).await);
+        }
+
+        res
+    }
+}
+
+#[async_trait]
+impl<P: ProfileProvider + ?Sized> ProfileProvider for Box<P> {
+    fn name(&self) -> &str {
+        (**self).name()
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        (**self).get_uuid_by_username(username).await
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        (**self).get_username_by_uuid(uuid).await
+    }
+
+    async fn get_usernames_by_uuids(
+        &self,
+        uuids: &[MinecraftUuid],
+    ) -> HashMap<MinecraftUuid, Result<String>> {
+        (**self).get_usernames_by_uuids(uuids).await
+    }
+}
@@ -0,0 +1,26 @@
+use super::ProfileProvider;
+use crate::models::{ErrorResponse, UUIDResponse};
+use crate::{endpoints, get, MinecraftUuid};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Resolves profiles using the official Mojang API.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MojangProvider;
+
+#[async_trait]
+impl ProfileProvider for MojangProvider {
+    fn name(&self) -> &str {
+        "mojang"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        let res: UUIDResponse = get::<_, ErrorResponse>(&endpoints::usernames(username)).await?;
+        Ok(res.id.parse()?)
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        let res: UUIDResponse = get::<_, ErrorResponse>(&endpoints::uids(&uuid.simple())).await?;
+        Ok(res.name)
+    }
+}
@@ -0,0 +1,83 @@
+use super::ProfileProvider;
+use crate::error::APIError;
+use crate::MinecraftUuid;
+use anyhow::Result;
+use async_trait::async_trait;
+use md5::{Digest, Md5};
+
+/// Generates the UUID an offline-mode server assigns to
+/// the player with the given username.
+///
+/// This is a version 3 UUID built from the MD5 hash of
+/// `OfflinePlayer:<username>`. Keep in mind that the
+/// username is case sensitive here.
+///
+/// # Example
+/// ```
+/// # use minecraft_uuid::*;
+/// let uuid = offline_uuid("Notch");
+/// assert_eq!(uuid.to_string(), "b50ad385829d3141a2167e7d7539ba7f");
+/// ```
+pub fn offline_uuid(username: &str) -> MinecraftUuid {
+    let mut hash = Md5::digest(format!("OfflinePlayer:{username}"));
+
+    hash[6] = (hash[6] & 0x0f) | 0x30;
+    hash[8] = (hash[8] & 0x3f) | 0x80;
+
+    MinecraftUuid::from_u128(u128::from_be_bytes(hash.into()))
+}
+
+/// Returns `true` if the given UUID is a version 3 UUID
+/// like the ones generated by [`offline_uuid`].
+///
+/// Accounts authenticated by Mojang always have version 4 UUIDs.
+pub fn is_offline_uuid(uuid: &MinecraftUuid) -> bool {
+    uuid.version() == 3
+}
+
+/// Resolves usernames to offline-mode UUIDs without
+/// any network requests.
+///
+/// Because offline UUIDs are hashes, they can not be resolved
+/// back to a username. [`ProfileProvider::get_username_by_uuid`]
+/// therefore always fails with a `501` [`APIError`], so that a
+/// [`FallbackProvider`](super::FallbackProvider) continues with
+/// the next provider.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OfflineProvider;
+
+#[async_trait]
+impl ProfileProvider for OfflineProvider {
+    fn name(&self) -> &str {
+        "offline"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        Ok(offline_uuid(username))
+    }
+
+    async fn get_username_by_uuid(&self, _uuid: &MinecraftUuid) -> Result<String> {
+        let err = APIError::new(
+            501,
+            "NotImplemented",
+            "Offline UUIDs can not be resolved to a username.",
+        );
+        Err(err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offline_uuid() {
+        let uuid = offline_uuid("Notch");
+        assert_eq!(uuid.to_string(), "b50ad385829d3141a2167e7d7539ba7f");
+        assert_ne!(offline_uuid("notch"), uuid);
+        assert!(is_offline_uuid(&uuid));
+
+        let uuid = "c3371e36f2884eaeb9d5b90e47258444".parse().unwrap();
+        assert!(!is_offline_uuid(&uuid));
+    }
+}
@@ -0,0 +1,33 @@
+use super::ProfileProvider;
+use crate::models::{PlayerDbErrorResponse, PlayerDbResponse};
+use crate::{endpoints, get, MinecraftUuid};
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Resolves profiles using the [PlayerDB](https://playerdb.co)
+/// mirror API.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlayerDbProvider;
+
+impl PlayerDbProvider {
+    async fn lookup(&self, username_or_uuid: &str) -> Result<PlayerDbResponse> {
+        get::<_, PlayerDbErrorResponse>(&endpoints::playerdb_player(username_or_uuid)).await
+    }
+}
+
+#[async_trait]
+impl ProfileProvider for PlayerDbProvider {
+    fn name(&self) -> &str {
+        "playerdb"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        let res = self.lookup(username).await?;
+        Ok(res.data.player.raw_id.parse()?)
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        let res = self.lookup(&uuid.simple()).await?;
+        Ok(res.data.player.username)
+    }
+}
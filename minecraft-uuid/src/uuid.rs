@@ -0,0 +1,236 @@
+use crate::error::ParseUuidError;
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// A Minecraft account UUID.
+///
+/// It can be parsed from both the undashed form used by the
+/// Mojang API (`c3371e36f2884eaeb9d5b90e47258444`) and the
+/// hyphenated form used in game
+/// (`c3371e36-f288-4eae-b9d5-b90e47258444`).
+///
+/// [`Display`](fmt::Display) produces the undashed form. Use the
+/// alternate flag (`{:#}`) or [`MinecraftUuid::hyphenated`] to get
+/// the hyphenated form.
+///
+/// # Example
+/// ```
+/// # use minecraft_uuid::MinecraftUuid;
+/// let uuid: MinecraftUuid = "c3371e36-f288-4eae-b9d5-b90e47258444".parse().unwrap();
+/// assert_eq!(uuid.to_string(), "c3371e36f2884eaeb9d5b90e47258444");
+/// assert_eq!(format!("{uuid:#}"), "c3371e36-f288-4eae-b9d5-b90e47258444");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MinecraftUuid(u128);
+
+impl MinecraftUuid {
+    pub const fn from_u128(v: u128) -> Self {
+        Self(v)
+    }
+
+    pub const fn as_u128(&self) -> u128 {
+        self.0
+    }
+
+    /// The version of the UUID.
+    ///
+    /// Accounts authenticated by Mojang have version 4 UUIDs,
+    /// offline-mode accounts have version 3 UUIDs.
+    pub const fn version(&self) -> u8 {
+        ((self.0 >> 76) & 0xf) as u8
+    }
+
+    /// The undashed form of the UUID as used by the Mojang API.
+    pub fn simple(&self) -> String {
+        format!("{self}")
+    }
+
+    /// The hyphenated form of the UUID as used in game.
+    pub fn hyphenated(&self) -> String {
+        format!("{self:#}")
+    }
+}
+
+impl FromStr for MinecraftUuid {
+    type Err = ParseUuidError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let simple = match s.len() {
+            32 => s.to_owned(),
+            36 if [8, 13, 18, 23].iter().all(|&i| s.as_bytes()[i] == b'-') => s.replace('-', ""),
+            _ => return Err(ParseUuidError::new(s)),
+        };
+
+        // Dashes at other positions would otherwise be removed as well.
+        if simple.len() != 32 || !simple.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(ParseUuidError::new(s));
+        }
+
+        u128::from_str_radix(&simple, 16)
+            .map(Self)
+            .map_err(|_| ParseUuidError::new(s))
+    }
+}
+
+impl TryFrom<&str> for MinecraftUuid {
+    type Error = ParseUuidError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl TryFrom<String> for MinecraftUuid {
+    type Error = ParseUuidError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MinecraftUuid> for String {
+    fn from(uuid: MinecraftUuid) -> Self {
+        uuid.simple()
+    }
+}
+
+impl fmt::Display for MinecraftUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = format!("{:032x}", self.0);
+        if f.alternate() {
+            write!(
+                f,
+                "{}-{}-{}-{}-{}",
+                &s[..8],
+                &s[8..12],
+                &s[12..16],
+                &s[16..20],
+                &s[20..]
+            )
+        } else {
+            f.write_str(&s)
+        }
+    }
+}
+
+impl fmt::Debug for MinecraftUuid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MinecraftUuid({self:#})")
+    }
+}
+
+impl Serialize for MinecraftUuid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MinecraftUuid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct UuidVisitor;
+
+        impl Visitor<'_> for UuidVisitor {
+            type Value = MinecraftUuid;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a hyphenated or undashed UUID string")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+                v.parse().map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_str(UuidVisitor)
+    }
+}
+
+#[cfg(feature = "sqlx")]
+mod sqlx_impl {
+    use super::MinecraftUuid;
+    use sqlx::encode::IsNull;
+    use sqlx::error::BoxDynError;
+    use sqlx::{Database, Decode, Encode, Type};
+
+    /// UUIDs are stored in their undashed form. Both forms
+    /// are accepted when decoding.
+    impl<DB: Database> Type<DB> for MinecraftUuid
+    where
+        String: Type<DB>,
+    {
+        fn type_info() -> DB::TypeInfo {
+            <String as Type<DB>>::type_info()
+        }
+
+        fn compatible(ty: &DB::TypeInfo) -> bool {
+            <String as Type<DB>>::compatible(ty)
+        }
+    }
+
+    impl<'q, DB: Database> Encode<'q, DB> for MinecraftUuid
+    where
+        String: Encode<'q, DB>,
+    {
+        fn encode_by_ref(
+            &self,
+            buf: &mut <DB as Database>::ArgumentBuffer<'q>,
+        ) -> Result<IsNull, BoxDynError> {
+            self.simple().encode_by_ref(buf)
+        }
+    }
+
+    impl<'r, DB: Database> Decode<'r, DB> for MinecraftUuid
+    where
+        &'r str: Decode<'r, DB>,
+    {
+        fn decode(value: <DB as Database>::ValueRef<'r>) -> Result<Self, BoxDynError> {
+            let s = <&str as Decode<DB>>::decode(value)?;
+            Ok(s.parse()?)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIMPLE: &str = "c3371e36f2884eaeb9d5b90e47258444";
+    const HYPHENATED: &str = "c3371e36-f288-4eae-b9d5-b90e47258444";
+
+    #[test]
+    fn test_parse() {
+        let a: MinecraftUuid = SIMPLE.parse().unwrap();
+        let b: MinecraftUuid = HYPHENATED.parse().unwrap();
+        assert_eq!(a, b);
+        assert_eq!(a, SIMPLE.to_uppercase().parse().unwrap());
+        assert_eq!(a.version(), 4);
+
+        assert!("invaliduuid".parse::<MinecraftUuid>().is_err());
+        assert!("c3371e36f2884eaeb9d5b90e4725844g"
+            .parse::<MinecraftUuid>()
+            .is_err());
+        assert!("c3371e36-f288-4eae-b9d5-b90e4725844-"
+            .parse::<MinecraftUuid>()
+            .is_err());
+        assert!("c3371e36-f2884-eae-b9d5-b90e47258444"
+            .parse::<MinecraftUuid>()
+            .is_err());
+        assert!("+3371e36f2884eaeb9d5b90e47258444"
+            .parse::<MinecraftUuid>()
+            .is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let uuid: MinecraftUuid = SIMPLE.parse().unwrap();
+        assert_eq!(uuid.to_string(), SIMPLE);
+        assert_eq!(uuid.simple(), SIMPLE);
+        assert_eq!(uuid.hyphenated(), HYPHENATED);
+        assert_eq!(format!("{uuid:#}"), HYPHENATED);
+
+        let uuid = MinecraftUuid::from_u128(1);
+        assert_eq!(uuid.to_string(), "00000000000000000000000000000001");
+    }
+}
@@ -0,0 +1 @@
+ALTER TABLE users CHANGE mc_name mc_uuid VARCHAR(64);
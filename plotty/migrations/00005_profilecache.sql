@@ -0,0 +1,8 @@
+CREATE TABLE profile_cache (
+    mc_uuid VARCHAR(64) NOT NULL,
+    mc_name VARCHAR(64) NOT NULL,
+    fetched_at BIGINT UNSIGNED NOT NULL,
+
+    PRIMARY KEY (mc_uuid),
+    INDEX (mc_name)
+);
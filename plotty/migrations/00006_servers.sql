@@ -0,0 +1,15 @@
+ALTER TABLE plots ADD COLUMN server VARCHAR(64) NOT NULL DEFAULT 'default';
+
+CREATE TABLE whitelists (
+    user_id BIGINT UNSIGNED NOT NULL,
+    server VARCHAR(64) NOT NULL,
+
+    PRIMARY KEY (user_id, server),
+    FOREIGN KEY (user_id)
+        REFERENCES users(user_id)
+        ON DELETE CASCADE
+);
+
+INSERT INTO whitelists (user_id, server)
+SELECT user_id, 'default'
+FROM users;
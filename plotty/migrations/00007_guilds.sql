@@ -0,0 +1,36 @@
+ALTER TABLE plots DROP FOREIGN KEY plots_ibfk_1;
+ALTER TABLE plot_ids DROP FOREIGN KEY plot_ids_ibfk_1;
+ALTER TABLE whitelists DROP FOREIGN KEY whitelists_ibfk_1;
+
+-- Rows created before multiple guilds were supported are
+-- assigned to the configured guild on startup.
+ALTER TABLE users
+    ADD COLUMN guild_id BIGINT UNSIGNED NOT NULL DEFAULT 0 FIRST,
+    DROP PRIMARY KEY,
+    ADD PRIMARY KEY (guild_id, user_id);
+
+ALTER TABLE plots
+    ADD COLUMN guild_id BIGINT UNSIGNED NOT NULL DEFAULT 0,
+    ADD INDEX (guild_id, user_id),
+    ADD FOREIGN KEY (guild_id, user_id)
+        REFERENCES users(guild_id, user_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE;
+
+ALTER TABLE plot_ids
+    ADD COLUMN guild_id BIGINT UNSIGNED NOT NULL DEFAULT 0 FIRST,
+    DROP PRIMARY KEY,
+    ADD PRIMARY KEY (guild_id, user_id),
+    ADD FOREIGN KEY (guild_id, user_id)
+        REFERENCES users(guild_id, user_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE;
+
+ALTER TABLE whitelists
+    ADD COLUMN guild_id BIGINT UNSIGNED NOT NULL DEFAULT 0 FIRST,
+    DROP PRIMARY KEY,
+    ADD PRIMARY KEY (guild_id, user_id, server),
+    ADD FOREIGN KEY (guild_id, user_id)
+        REFERENCES users(guild_id, user_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE;
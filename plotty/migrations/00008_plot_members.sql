@@ -0,0 +1,10 @@
+CREATE TABLE plot_members (
+    plot_id VARCHAR(64) NOT NULL,
+    mc_uuid VARCHAR(64) NOT NULL,
+
+    PRIMARY KEY (plot_id, mc_uuid),
+    FOREIGN KEY (plot_id)
+        REFERENCES plots(plot_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+);
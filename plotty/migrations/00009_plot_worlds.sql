@@ -0,0 +1,3 @@
+-- Plots created before the world was stored are assumed to be
+-- in the overworld, which was the default.
+ALTER TABLE plots ADD COLUMN world VARCHAR(64) NOT NULL DEFAULT 'world';
@@ -0,0 +1,11 @@
+CREATE TABLE plot_flags (
+    plot_id VARCHAR(64) NOT NULL,
+    flag VARCHAR(64) NOT NULL,
+    value TEXT NOT NULL,
+
+    PRIMARY KEY (plot_id, flag),
+    FOREIGN KEY (plot_id)
+        REFERENCES plots(plot_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+);
@@ -0,0 +1,3 @@
+-- Pending plots are waiting for staff approval and do not
+-- exist in WorldGuard yet.
+ALTER TABLE plots ADD COLUMN pending BOOLEAN NOT NULL DEFAULT FALSE;
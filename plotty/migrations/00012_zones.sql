@@ -0,0 +1,10 @@
+CREATE TABLE zones (
+    server VARCHAR(64) NOT NULL,
+    name VARCHAR(64) NOT NULL,
+    world VARCHAR(64) NOT NULL,
+    -- The corners as JSON array of [x, z] pairs.
+    points TEXT NOT NULL,
+    approval BOOLEAN NOT NULL DEFAULT FALSE,
+
+    PRIMARY KEY (server, name)
+);
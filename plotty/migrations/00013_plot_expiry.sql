@@ -0,0 +1,26 @@
+-- When bound players have last been seen online. Keyed by the
+-- Minecraft account, as it may be bound in multiple guilds.
+CREATE TABLE user_activity (
+    mc_uuid VARCHAR(64) NOT NULL,
+    seen_at DATETIME NOT NULL,
+    -- Set once the owner has been warned about the expiry
+    -- of their plots, reset when they are seen again.
+    warned_at DATETIME NULL,
+
+    PRIMARY KEY (mc_uuid)
+);
+
+CREATE TABLE expiry_exemptions (
+    guild_id BIGINT UNSIGNED NOT NULL,
+    user_id BIGINT UNSIGNED NOT NULL,
+
+    PRIMARY KEY (guild_id, user_id),
+    FOREIGN KEY (guild_id, user_id)
+        REFERENCES users(guild_id, user_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+);
+
+-- Expired plots have been removed from WorldGuard but can be
+-- restored until they are purged.
+ALTER TABLE plots ADD COLUMN expired_at DATETIME NULL;
@@ -0,0 +1,3 @@
+-- Deleted plots are kept with their members and flags so that
+-- they can be restored until they are purged.
+ALTER TABLE plots ADD COLUMN deleted_at DATETIME NULL;
@@ -0,0 +1,18 @@
+-- Previous perimeters of plots, numbered per plot.
+CREATE TABLE plot_revisions (
+    plot_id VARCHAR(64) NOT NULL,
+    revision INT UNSIGNED NOT NULL,
+    world VARCHAR(64) NOT NULL,
+    ax INT NOT NULL,
+    az INT NOT NULL,
+    bx INT NOT NULL,
+    bz INT NOT NULL,
+    -- When the perimeter has been replaced.
+    created_at DATETIME NOT NULL,
+
+    PRIMARY KEY (plot_id, revision),
+    FOREIGN KEY (plot_id)
+        REFERENCES plots(plot_id)
+        ON DELETE CASCADE
+        ON UPDATE CASCADE
+);
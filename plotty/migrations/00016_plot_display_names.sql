@@ -0,0 +1,5 @@
+-- Names chosen by the owner which are shown instead of the
+-- region ID. Unique, as they can be used instead of the ID.
+ALTER TABLE plots
+    ADD COLUMN display_name VARCHAR(64) NULL,
+    ADD UNIQUE INDEX (display_name);
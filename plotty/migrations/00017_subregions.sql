@@ -0,0 +1,5 @@
+-- Subregions lie inside a plot of the same owner. There is no
+-- foreign key, as MySQL can not cascade renames within a table.
+ALTER TABLE plots
+    ADD COLUMN parent_id VARCHAR(64) NULL,
+    ADD INDEX (parent_id);
@@ -0,0 +1,7 @@
+-- The name an account has been bound with. Offline-mode accounts can
+-- not be resolved by any service, so their name has to be kept here.
+ALTER TABLE users ADD COLUMN mc_name VARCHAR(64) NULL;
+
+UPDATE users u
+    JOIN profile_cache p ON p.mc_uuid = u.mc_uuid
+    SET u.mc_name = p.mc_name;
@@ -0,0 +1,5 @@
+-- Display names only have to be unique within a guild. Region IDs
+-- stay globally unique, as guilds may share servers.
+ALTER TABLE plots
+    DROP INDEX display_name,
+    ADD UNIQUE INDEX (guild_id, display_name);
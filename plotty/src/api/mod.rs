@@ -0,0 +1,221 @@
+//! HTTP API for reading users and plots and managing plots
+//! without Discord.
+
+mod plots;
+mod users;
+
+use crate::conf;
+use crate::db::Database;
+use crate::idcache::IdCache;
+use crate::mc::Servers;
+use crate::shutdown::Shutdown;
+use axum::extract::{FromRequestParts, RawPathParams, Request, State};
+use axum::http::request::Parts;
+use axum::http::{header, StatusCode};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use log::error;
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "plotty", description = "Plots and account bindings managed by plotty."),
+    paths(
+        users::list,
+        users::get,
+        users::plots,
+        plots::list,
+        plots::get,
+        plots::members,
+        plots::create,
+        plots::redefine,
+        plots::delete,
+    ),
+    modifiers(&BearerAuth)
+)]
+struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "token",
+            SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+        );
+    }
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub cfg: Arc<conf::Shared>,
+    pub db: Arc<Database>,
+    pub rc: Arc<Servers>,
+    pub idcache: Arc<IdCache>,
+    pub shutdown: Arc<Shutdown>,
+    /// Used to post plots which need approval to the review channel.
+    pub http: Arc<serenity::http::Http>,
+}
+
+impl AppState {
+    /// Returns the settings of the given guild and the servers
+    /// available to it.
+    fn guild(&self, id: u64) -> Result<(conf::GuildSettings, Servers), Error> {
+        let guild = self
+            .cfg
+            .get()
+            .guild(id)
+            .ok_or_else(|| Error::new(StatusCode::NOT_FOUND, "Unknown guild."))?;
+        let rc = self.rc.only(&guild.servers);
+        Ok((guild, rc))
+    }
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route(
+            "/api/openapi.json",
+            get(|| async { Json(ApiDoc::openapi()) }),
+        )
+        .route("/api/guilds/{guild_id}/users", get(users::list))
+        .route("/api/guilds/{guild_id}/users/{user_id}", get(users::get))
+        .route(
+            "/api/guilds/{guild_id}/users/{user_id}/plots",
+            get(users::plots),
+        )
+        .route(
+            "/api/guilds/{guild_id}/plots",
+            get(plots::list).post(plots::create),
+        )
+        .route(
+            "/api/guilds/{guild_id}/plots/{name}",
+            get(plots::get).put(plots::redefine).delete(plots::delete),
+        )
+        .route(
+            "/api/guilds/{guild_id}/plots/{name}/members",
+            get(plots::members),
+        )
+        .layer(middleware::from_fn_with_state(state.clone(), track))
+        .with_state(state)
+}
+
+/// Lets shutting down wait for running requests and rejects new
+/// ones in the meantime.
+async fn track(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let Some(_guard) = state.shutdown.start() else {
+        return Error::new(StatusCode::SERVICE_UNAVAILABLE, "plotty is shutting down.")
+            .into_response();
+    };
+    next.run(req).await
+}
+
+pub async fn serve(address: String, state: AppState) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+/// Rejects requests without one of the configured API tokens, or
+/// with a token which is not bound to the guild of the request.
+pub struct Authorized;
+
+impl FromRequestParts<AppState> for Authorized {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &AppState) -> Result<Self, Error> {
+        let params = RawPathParams::from_request_parts(parts, state)
+            .await
+            .map_err(|_| Error::new(StatusCode::BAD_REQUEST, "Invalid path."))?;
+        let guild_id = params
+            .iter()
+            .find(|(key, _)| *key == "guild_id")
+            .and_then(|(_, value)| value.parse::<u64>().ok());
+
+        let token = parts
+            .headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "));
+
+        match (token, guild_id) {
+            (Some(token), Some(guild_id)) if state.cfg.get().api.authorizes(token, guild_id) => {
+                Ok(Authorized)
+            }
+            _ => Err(Error::new(
+                StatusCode::UNAUTHORIZED,
+                "A valid API token for this guild is required.",
+            )),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    error: String,
+}
+
+/// An error response with a JSON body.
+#[derive(Debug)]
+pub struct Error {
+    status: StatusCode,
+    message: String,
+}
+
+impl Error {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        if let Some(rejected) = err.downcast_ref::<crate::plots::Rejected>() {
+            return Self::new(StatusCode::UNPROCESSABLE_ENTITY, rejected.to_string());
+        }
+
+        error!("API request failed: {err}");
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "Internal server error.")
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let body = ErrorBody {
+            error: self.message,
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn openapi() {
+        let doc = ApiDoc::openapi();
+        assert!(doc.paths.paths.contains_key("/api/guilds/{guild_id}/plots"));
+        assert!(doc
+            .components
+            .is_some_and(|c| c.security_schemes.contains_key("token")));
+    }
+
+    #[test]
+    fn error_status() {
+        let err: Error = anyhow::Error::from(crate::plots::Rejected("collision".into())).into();
+        assert_eq!(err.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(err.message, "collision");
+
+        let err: Error = anyhow::anyhow!("RCON connection failed").into();
+        assert_eq!(err.status, StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
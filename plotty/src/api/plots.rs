@@ -0,0 +1,319 @@
+use super::{AppState, Authorized, Error, ErrorBody};
+use crate::models::{self, Point, Region};
+use crate::plots;
+use crate::review;
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Serialize, ToSchema)]
+pub struct Plot {
+    name: String,
+    /// The name chosen by the owner, if any.
+    display_name: Option<String>,
+    /// The Discord ID of the owner as a string. Not set
+    /// for plots kept after their owner unbound.
+    owner: Option<String>,
+    server: String,
+    world: String,
+    perimeter: Perimeter,
+    /// The area in m².
+    area: i64,
+    /// Whether the plot is waiting for staff approval.
+    pending: bool,
+    /// The plot this region is a subregion of.
+    parent: Option<String>,
+}
+
+impl From<&Region> for Plot {
+    fn from(region: &Region) -> Self {
+        Self {
+            name: region.name.clone(),
+            display_name: region.display_name.clone(),
+            owner: region.owner.map(|id| id.to_string()),
+            server: region.server.clone(),
+            world: region.world.clone(),
+            perimeter: Perimeter {
+                x1: region.perimeter.0 .0,
+                z1: region.perimeter.0 .1,
+                x2: region.perimeter.1 .0,
+                z2: region.perimeter.1 .1,
+            },
+            area: region.perimeter.size(),
+            pending: region.pending,
+            parent: region.parent.clone(),
+        }
+    }
+}
+
+/// Two opposite corners of a plot.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct Perimeter {
+    x1: i64,
+    z1: i64,
+    x2: i64,
+    z2: i64,
+}
+
+impl From<Perimeter> for models::Perimeter {
+    fn from(p: Perimeter) -> Self {
+        models::Perimeter(Point(p.x1, p.z1), Point(p.x2, p.z2))
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Member {
+    uuid: String,
+    /// Not set if the name could not be resolved.
+    name: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// Only list plots on this server.
+    server: Option<String>,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreatePlot {
+    /// The Discord ID of the owner, who must have bound
+    /// a Minecraft account.
+    owner: String,
+    /// Defaults to the first server of the guild.
+    server: Option<String>,
+    /// Defaults to `world`.
+    world: Option<String>,
+    perimeter: Perimeter,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RedefinePlot {
+    /// Only needed to correct the world of plots created
+    /// before the world was stored.
+    world: Option<String>,
+    perimeter: Perimeter,
+}
+
+/// Lists all plots of the guild.
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{guild_id}/plots",
+    params(("guild_id" = u64, Path), ListQuery),
+    responses(
+        (status = 200, body = [Plot]),
+        (status = 404, body = ErrorBody),
+    ),
+    tag = "plots"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    Path(guild_id): Path<u64>,
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<Plot>>, Error> {
+    let (guild, _) = state.guild(guild_id)?;
+
+    let plots = state
+        .db
+        .get_plots()
+        .await?
+        .iter()
+        .filter(|p| p.guild_id == guild.id)
+        .filter(|p| query.server.as_ref().is_none_or(|s| &p.server == s))
+        .map(Plot::from)
+        .collect();
+
+    Ok(Json(plots))
+}
+
+/// Returns a single plot.
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{guild_id}/plots/{name}",
+    params(("guild_id" = u64, Path), ("name" = String, Path)),
+    responses(
+        (status = 200, body = Plot),
+        (status = 404, body = ErrorBody),
+    ),
+    tag = "plots"
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    Path((guild_id, name)): Path<(u64, String)>,
+) -> Result<Json<Plot>, Error> {
+    let region = find(&state, guild_id, &name).await?;
+    Ok(Json(Plot::from(&region)))
+}
+
+/// Lists the members of a plot which have been added via plotty.
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{guild_id}/plots/{name}/members",
+    params(("guild_id" = u64, Path), ("name" = String, Path)),
+    responses(
+        (status = 200, body = [Member]),
+        (status = 404, body = ErrorBody),
+    ),
+    tag = "plots"
+)]
+pub async fn members(
+    State(state): State<AppState>,
+    Path((guild_id, name)): Path<(u64, String)>,
+) -> Result<Json<Vec<Member>>, Error> {
+    let region = find(&state, guild_id, &name).await?;
+
+    let mut res = Vec::new();
+    for uuid in state.db.get_plot_members(&region.name).await? {
+        res.push(Member {
+            uuid: format!("{uuid:#}"),
+            name: state.idcache.get_username_by_uuid(&uuid).await.ok(),
+        });
+    }
+
+    Ok(Json(res))
+}
+
+/// Creates a plot for a user.
+///
+/// Plots which need to be approved are posted to the review channel
+/// and only created once approved by the staff.
+#[utoipa::path(
+    post,
+    path = "/api/guilds/{guild_id}/plots",
+    params(("guild_id" = u64, Path)),
+    request_body = CreatePlot,
+    responses(
+        (status = 201, body = Plot),
+        (status = 202, description = "The plot is waiting for approval.", body = Plot),
+        (status = 400, body = ErrorBody),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 422, body = ErrorBody),
+    ),
+    security(("token" = [])),
+    tag = "plots"
+)]
+pub async fn create(
+    _: Authorized,
+    State(state): State<AppState>,
+    Path(guild_id): Path<u64>,
+    Json(body): Json<CreatePlot>,
+) -> Result<(StatusCode, Json<Plot>), Error> {
+    let (guild, rc) = state.guild(guild_id)?;
+
+    let owner: u64 = body
+        .owner
+        .parse()
+        .map_err(|_| Error::new(StatusCode::BAD_REQUEST, "The owner is not a Discord ID."))?;
+
+    let uuid = state
+        .db
+        .get_user_by_id(guild.id, owner)
+        .await?
+        .ok_or_else(|| {
+            Error::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The owner has not bound a Minecraft account.",
+            )
+        })?;
+
+    let username = state
+        .idcache
+        .get_username_by_uuid(&uuid)
+        .await?
+        .to_lowercase();
+
+    let region = plots::create(
+        &state.db,
+        &rc,
+        &guild,
+        owner,
+        &username,
+        body.server.as_deref(),
+        body.world.as_deref().unwrap_or("world"),
+        body.perimeter.into(),
+    )
+    .await?;
+
+    if region.pending {
+        review::request(&state.http, &state.db, &guild, &region).await?;
+        return Ok((StatusCode::ACCEPTED, Json(Plot::from(&region))));
+    }
+
+    Ok((StatusCode::CREATED, Json(Plot::from(&region))))
+}
+
+/// Updates the perimeter of a plot.
+#[utoipa::path(
+    put,
+    path = "/api/guilds/{guild_id}/plots/{name}",
+    params(("guild_id" = u64, Path), ("name" = String, Path)),
+    request_body = RedefinePlot,
+    responses(
+        (status = 200, body = Plot),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+        (status = 422, body = ErrorBody),
+    ),
+    security(("token" = [])),
+    tag = "plots"
+)]
+pub async fn redefine(
+    _: Authorized,
+    State(state): State<AppState>,
+    Path((guild_id, name)): Path<(u64, String)>,
+    Json(body): Json<RedefinePlot>,
+) -> Result<Json<Plot>, Error> {
+    let (guild, rc) = state.guild(guild_id)?;
+    let region = find(&state, guild_id, &name).await?;
+
+    let region = plots::redefine(
+        &state.db,
+        &rc,
+        &guild,
+        region,
+        body.world.as_deref(),
+        body.perimeter.into(),
+    )
+    .await?;
+
+    Ok(Json(Plot::from(&region)))
+}
+
+/// Deletes a plot. It can be restored by its owner until it is
+/// purged after `trash.retention_days`.
+#[utoipa::path(
+    delete,
+    path = "/api/guilds/{guild_id}/plots/{name}",
+    params(("guild_id" = u64, Path), ("name" = String, Path)),
+    responses(
+        (status = 204),
+        (status = 401, body = ErrorBody),
+        (status = 404, body = ErrorBody),
+    ),
+    security(("token" = [])),
+    tag = "plots"
+)]
+pub async fn delete(
+    _: Authorized,
+    State(state): State<AppState>,
+    Path((guild_id, name)): Path<(u64, String)>,
+) -> Result<StatusCode, Error> {
+    let (_, rc) = state.guild(guild_id)?;
+    let region = find(&state, guild_id, &name).await?;
+
+    plots::delete(&state.db, &rc, &region).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn find(state: &AppState, guild_id: u64, name: &str) -> Result<Region, Error> {
+    let (guild, _) = state.guild(guild_id)?;
+
+    state
+        .db
+        .find_plot(guild.id, &name.to_lowercase())
+        .await?
+        .ok_or_else(|| Error::new(StatusCode::NOT_FOUND, "Unknown plot."))
+}
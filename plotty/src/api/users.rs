@@ -0,0 +1,120 @@
+use super::plots::Plot;
+use super::{AppState, Error, ErrorBody};
+use crate::models;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::Json;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// A Discord account bound to a Minecraft account.
+#[derive(Serialize, ToSchema)]
+pub struct User {
+    /// The Discord ID as a string, as it exceeds the
+    /// safe integer range of JavaScript.
+    discord_id: String,
+    minecraft_uuid: String,
+    /// Not set if the name could not be resolved.
+    minecraft_name: Option<String>,
+}
+
+impl User {
+    async fn from_model(state: &AppState, user: &models::User) -> Self {
+        Self {
+            discord_id: user.discord_id.to_string(),
+            minecraft_uuid: format!("{:#}", user.minecraft_uid),
+            minecraft_name: state
+                .idcache
+                .get_username_by_uuid(&user.minecraft_uid)
+                .await
+                .ok(),
+        }
+    }
+}
+
+/// Lists all bound users of the guild.
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{guild_id}/users",
+    params(("guild_id" = u64, Path)),
+    responses(
+        (status = 200, body = [User]),
+        (status = 404, body = ErrorBody),
+    ),
+    tag = "users"
+)]
+pub async fn list(
+    State(state): State<AppState>,
+    Path(guild_id): Path<u64>,
+) -> Result<Json<Vec<User>>, Error> {
+    let (guild, _) = state.guild(guild_id)?;
+
+    let mut res = Vec::new();
+    for user in state.db.list_users().await? {
+        if user.guild_id == guild.id {
+            res.push(User::from_model(&state, &user).await);
+        }
+    }
+
+    Ok(Json(res))
+}
+
+/// Returns a single bound user.
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{guild_id}/users/{user_id}",
+    params(("guild_id" = u64, Path), ("user_id" = u64, Path)),
+    responses(
+        (status = 200, body = User),
+        (status = 404, body = ErrorBody),
+    ),
+    tag = "users"
+)]
+pub async fn get(
+    State(state): State<AppState>,
+    Path((guild_id, user_id)): Path<(u64, u64)>,
+) -> Result<Json<User>, Error> {
+    let (guild, _) = state.guild(guild_id)?;
+
+    let uuid = state
+        .db
+        .get_user_by_id(guild.id, user_id)
+        .await?
+        .ok_or_else(|| Error::new(StatusCode::NOT_FOUND, "Unknown user."))?;
+
+    let user = models::User {
+        guild_id: guild.id,
+        discord_id: user_id,
+        minecraft_uid: uuid,
+    };
+
+    Ok(Json(User::from_model(&state, &user).await))
+}
+
+/// Lists the plots owned by a user.
+#[utoipa::path(
+    get,
+    path = "/api/guilds/{guild_id}/users/{user_id}/plots",
+    params(("guild_id" = u64, Path), ("user_id" = u64, Path)),
+    responses(
+        (status = 200, body = [Plot]),
+        (status = 404, body = ErrorBody),
+    ),
+    tag = "users"
+)]
+pub async fn plots(
+    State(state): State<AppState>,
+    Path((guild_id, user_id)): Path<(u64, u64)>,
+) -> Result<Json<Vec<Plot>>, Error> {
+    let (guild, _) = state.guild(guild_id)?;
+
+    let plots = state
+        .db
+        .get_user_plots(guild.id, user_id)
+        .await?
+        .iter()
+        .map(Plot::from)
+        .collect();
+
+    Ok(Json(plots))
+}
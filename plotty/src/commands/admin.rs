@@ -0,0 +1,460 @@
+use crate::conf;
+use crate::db::Database;
+use crate::helpers::{self, FollowUpHelper, OptionsHelper};
+use crate::mc::Servers;
+use crate::models::Zone;
+use anyhow::Result;
+use log::info;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::guild::Member;
+use serenity::model::prelude::autocomplete::AutocompleteInteraction;
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::interaction::application_command::{
+    ApplicationCommandInteraction, CommandDataOption,
+};
+use serenity::model::Permissions;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("admin")
+        .description("Administrate plotty.")
+        .default_member_permissions(Permissions::ADMINISTRATOR)
+        .create_option(|o| {
+            o.name("reload")
+                .description("Reload the configuration.")
+                .kind(CommandOptionType::SubCommand)
+        })
+        .create_option(|o| {
+            o.name("zone")
+                .description("Manage zones in which plots may not be claimed freely.")
+                .kind(CommandOptionType::SubCommandGroup)
+                .create_sub_option(|so| {
+                    so.name("set")
+                        .description("Add or replace a zone.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("name")
+                                .description("The name of the zone.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("corners")
+                                .description(
+                                    "Two corners of a rectangle or the corners of a polygon, \
+                                     like `0,0 100,100`.",
+                                )
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("world")
+                                .description("The world of the zone. Defaults to `world`.")
+                                .kind(CommandOptionType::String)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("server")
+                                .description("The server of the zone.")
+                                .kind(CommandOptionType::String)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("approval")
+                                .description("Allow plots in the zone once approved by the staff.")
+                                .kind(CommandOptionType::Boolean)
+                        })
+                })
+                .create_sub_option(|so| {
+                    so.name("remove")
+                        .description("Remove a zone.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("name")
+                                .description("The name of the zone.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("server")
+                                .description("The server of the zone.")
+                                .kind(CommandOptionType::String)
+                                .set_autocomplete(true)
+                        })
+                })
+                .create_sub_option(|so| {
+                    so.name("list")
+                        .description("List all zones.")
+                        .kind(CommandOptionType::SubCommand)
+                })
+        })
+        .create_option(|o| {
+            o.name("expiry")
+                .description("Manage the expiry of plots of inactive users.")
+                .kind(CommandOptionType::SubCommandGroup)
+                .create_sub_option(|so| {
+                    so.name("exempt")
+                        .description("Keep the plots of a user even if they are inactive.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("user")
+                                .description("The user to exempt.")
+                                .kind(CommandOptionType::User)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|so| {
+                    so.name("unexempt")
+                        .description("Let the plots of a user expire again.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("user")
+                                .description("The user to no longer exempt.")
+                                .kind(CommandOptionType::User)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|so| {
+                    so.name("list")
+                        .description("List all exempted users.")
+                        .kind(CommandOptionType::SubCommand)
+                })
+        })
+}
+
+pub async fn run(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    cfg: &conf::Shared,
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    if !is_admin(command.member.as_ref(), guild) {
+        command
+            .followup_err(&ctx.http, "You are not allowed to use this command.")
+            .await?;
+        return Ok(());
+    }
+
+    let subcmd = command
+        .data
+        .options
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Response does not contain any sub command option."))?;
+
+    match subcmd.name.as_str() {
+        "reload" => reload(ctx, command, cfg).await,
+        "zone" => zone(ctx, command, subcmd, db, rc).await,
+        "expiry" => expiry(ctx, command, subcmd, db, guild).await,
+        _ => Err(anyhow::anyhow!("Unregistered sub command")),
+    }
+}
+
+pub async fn autocomplete(ctx: &Context, i: &AutocompleteInteraction, rc: &Servers) -> Result<()> {
+    if let Some(server) = i
+        .data
+        .options
+        .iter()
+        .flat_map(|s| &s.options)
+        .flat_map(|s| &s.options)
+        .find(|o| o.name == "server" && o.focused)
+    {
+        helpers::autocomplete_servers(ctx, i, rc, server).await?;
+    }
+
+    Ok(())
+}
+
+/// Server administrators are always allowed. Other members
+/// need one of the configured admin roles.
+pub fn is_admin(member: Option<&Member>, guild: &conf::GuildSettings) -> bool {
+    let Some(member) = member else {
+        return false;
+    };
+
+    member
+        .permissions
+        .is_some_and(|p| p.contains(Permissions::ADMINISTRATOR))
+        || member
+            .roles
+            .iter()
+            .any(|r| guild.admin_roles.contains(&r.0))
+}
+
+// ---- SUB COMMAND HANDLERS ----
+
+async fn reload(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    cfg: &conf::Shared,
+) -> Result<()> {
+    let new = conf::Config::load()?;
+    new.validate()?;
+    new.validate_roles(&ctx.http).await?;
+
+    let restart_required = cfg.replace(new);
+    info!("Config reloaded by {}", command.user.tag());
+
+    let mut msg = "The configuration has been reloaded. 🔄".to_owned();
+    if !restart_required.is_empty() {
+        msg.push_str(&format!(
+            "\n\nChanges to the following sections only take effect after a restart: {}",
+            restart_required
+                .iter()
+                .map(|s| format!("`{s}`"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    command.followup(&ctx.http, msg).await?;
+
+    Ok(())
+}
+
+async fn zone(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    rc: &Servers,
+) -> Result<()> {
+    let subcmd = subcmd
+        .options
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Response does not contain any sub command option."))?;
+
+    match subcmd.name.as_str() {
+        "set" => zone_set(ctx, command, subcmd, db, rc).await,
+        "remove" => zone_remove(ctx, command, subcmd, db, rc).await,
+        "list" => zone_list(ctx, command, db, rc).await,
+        _ => Err(anyhow::anyhow!("Unregistered sub-sub command")),
+    }
+}
+
+async fn zone_set(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    rc: &Servers,
+) -> Result<()> {
+    let name = subcmd
+        .get_required_option_by_name("name")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Name value is not a string"))?
+        .to_lowercase();
+
+    if name.is_empty()
+        || name.len() > 64
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        command
+            .followup_err(
+                &ctx.http,
+                "Zone names may only contain letters, digits, `-` and `_`.",
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let corners = subcmd
+        .get_required_option_by_name("corners")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Corners value is not a string"))?;
+
+    let points = match Zone::parse_points(corners) {
+        Ok(points) => points,
+        Err(err) => {
+            command.followup_err(&ctx.http, err).await?;
+            return Ok(());
+        }
+    };
+
+    let server = rc.get_or_default(subcmd.get_option_by_name("server").and_then(|v| v.as_str()))?;
+
+    let zone = Zone {
+        name,
+        server: server.name().to_owned(),
+        world: subcmd
+            .get_option_by_name("world")
+            .and_then(|v| v.as_str())
+            .unwrap_or("world")
+            .to_owned(),
+        points,
+        approval: subcmd
+            .get_option_by_name("approval")
+            .and_then(|v| v.as_bool())
+            .unwrap_or_default(),
+    };
+
+    db.set_zone(&zone).await?;
+    info!("Zone {} set by {}", zone.name, command.user.tag());
+
+    command
+        .followup(
+            &ctx.http,
+            format!("The zone `{}` has been saved. 🚧", zone.name),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn zone_remove(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    rc: &Servers,
+) -> Result<()> {
+    let name = subcmd
+        .get_required_option_by_name("name")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Name value is not a string"))?
+        .to_lowercase();
+
+    let server = rc.get_or_default(subcmd.get_option_by_name("server").and_then(|v| v.as_str()))?;
+
+    if !db.delete_zone(server.name(), &name).await? {
+        command
+            .followup_err(&ctx.http, "There is no zone with this name.")
+            .await?;
+        return Ok(());
+    }
+    info!("Zone {name} removed by {}", command.user.tag());
+
+    command
+        .followup(&ctx.http, format!("The zone `{name}` has been removed."))
+        .await?;
+
+    Ok(())
+}
+
+async fn zone_list(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    db: &Database,
+    rc: &Servers,
+) -> Result<()> {
+    let multiple_servers = rc.names().count() > 1;
+
+    let zones = db
+        .get_zones()
+        .await?
+        .iter()
+        .filter(|z| rc.get(&z.server).is_ok())
+        .map(|z| {
+            let corners = z
+                .points
+                .iter()
+                .map(|p| format!("{}, {}", p.0, p.1))
+                .collect::<Vec<_>>()
+                .join(" → ");
+            let mut line = format!("  ▫️ `{}` in {}", z.name, z.world);
+            if multiple_servers {
+                line.push_str(&format!(" on {}", z.server));
+            }
+            line.push_str(&format!(": `{corners}`"));
+            if z.approval {
+                line.push_str(" *(approval)*");
+            }
+            line
+        })
+        .collect::<Vec<_>>();
+
+    let description = match zones.is_empty() {
+        true => "There are no zones.".to_owned(),
+        false => format!("These are all zones:\n\n{}", zones.join("\n")),
+    };
+
+    command
+        .followup_embed(
+            &ctx.http,
+            CreateEmbed::default()
+                .color(Color::BLURPLE)
+                .description(description)
+                .to_owned(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn expiry(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let subcmd = subcmd
+        .options
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Response does not contain any sub command option."))?;
+
+    if subcmd.name == "list" {
+        let users = db
+            .get_expiry_exemptions(guild.id)
+            .await?
+            .iter()
+            .map(|id| format!("  ▫️ <@{id}>"))
+            .collect::<Vec<_>>();
+
+        let description = match users.is_empty() {
+            true => "No users are exempted.".to_owned(),
+            false => format!(
+                "The plots of these users do not expire:\n\n{}",
+                users.join("\n")
+            ),
+        };
+
+        command
+            .followup_embed(
+                &ctx.http,
+                CreateEmbed::default()
+                    .color(Color::BLURPLE)
+                    .description(description)
+                    .to_owned(),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let exempt = match subcmd.name.as_str() {
+        "exempt" => true,
+        "unexempt" => false,
+        _ => return Err(anyhow::anyhow!("Unregistered sub-sub command")),
+    };
+
+    let user_id: u64 = subcmd
+        .get_required_option_by_name("user")?
+        .as_str()
+        .and_then(|v| v.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("User value is not a user ID"))?;
+
+    if db.get_user_by_id(guild.id, user_id).await?.is_none() {
+        command
+            .followup_err(&ctx.http, "This user has not bound a Minecraft account.")
+            .await?;
+        return Ok(());
+    }
+
+    db.set_expiry_exempt(guild.id, user_id, exempt).await?;
+    info!(
+        "Expiry exemption of {user_id} set to {exempt} by {}",
+        command.user.tag()
+    );
+
+    let msg = match exempt {
+        true => format!("The plots of <@{user_id}> will no longer expire."),
+        false => format!("The plots of <@{user_id}> will expire again when inactive."),
+    };
+    command.followup(&ctx.http, msg).await?;
+
+    Ok(())
+}
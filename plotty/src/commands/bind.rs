@@ -1,10 +1,14 @@
+use crate::conf;
 use crate::db::Database;
-use crate::helpers::FollowUpHelper;
-use crate::idcache::get_uuid_by_username;
-use crate::mc::Rcon;
+use crate::helpers::{self, FollowUpHelper};
+use crate::idcache::IdCache;
+use crate::mc::{Rcon, Servers};
+use crate::shutdown::Shutdown;
+use crate::verification;
 use anyhow::Result;
 use minecraft_client_rs::Message;
 use serenity::builder::CreateApplicationCommand;
+use serenity::model::prelude::autocomplete::AutocompleteInteraction;
 use serenity::model::prelude::command::CommandOptionType;
 use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
 use serenity::prelude::Context;
@@ -19,13 +23,25 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 .kind(CommandOptionType::String)
                 .required(true)
         })
+        .create_option(|o| {
+            o.name("server")
+                .description("The server to be whitelisted on.")
+                .kind(CommandOptionType::String)
+                .set_autocomplete(true)
+                .required(false)
+        })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     ctx: &Context,
     command: &ApplicationCommandInteraction,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+    verification: &conf::Verification,
+    shutdown: &Shutdown,
 ) -> Result<()> {
     let mcname = &command
         .data
@@ -38,14 +54,23 @@ pub async fn run(
         .ok_or_else(|| anyhow::anyhow!("Username value is empty"))?
         .as_str()
         .ok_or_else(|| anyhow::anyhow!("Username value is not a string"))?
-        .to_lowercase();
+        .to_owned();
+
+    let server = command
+        .data
+        .options
+        .iter()
+        .find(|o| o.name == "server")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str());
+    let server = rc.get_or_default(server)?;
 
-    let uuid = get_uuid_by_username(mcname).await?;
+    let uuid = idcache.get_uuid_by_username(mcname).await?;
 
-    let res = db.get_user_by_mcname(&uuid).await?;
+    let res = db.get_user_by_uuid(guild.id, &uuid).await?;
 
     if let Some(res) = res {
-        if res == u64::from(command.user.id) {
+        if res != u64::from(command.user.id) {
             command
                 .followup_err(
                     &ctx.http,
@@ -56,24 +81,47 @@ pub async fn run(
         }
     }
 
-    let name_before = db.get_user_by_id(command.user.id).await?;
+    let uuid_before = db.get_user_by_id(guild.id, command.user.id).await?;
 
-    let msg: Message;
+    if verification.enabled
+        && uuid_before != Some(uuid)
+        && !verify(
+            ctx,
+            command,
+            server,
+            idcache,
+            verification,
+            mcname,
+            shutdown,
+        )
+        .await?
     {
-        let mut rc = rc
-            .get_conn()
-            .map_err(|e| anyhow::anyhow!("RCON connection failed: {}", e.to_string()))?;
-
-        if let Some(name_before) = name_before {
-            rc.cmd(&format!("whitelist remove {name_before}"))
-                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
-        }
+        return Ok(());
+    }
 
-        msg = rc
-            .cmd(&format!("whitelist add {mcname}"))
-            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    // Someone else may have bound the name while the verification was pending.
+    if verification.enabled
+        && db
+            .get_user_by_uuid(guild.id, &uuid)
+            .await?
+            .is_some_and(|id| id != u64::from(command.user.id))
+    {
+        command
+            .followup_err(
+                &ctx.http,
+                "This username is already registered by another user.",
+            )
+            .await?;
+        return Ok(());
     }
 
+    let name_before = match uuid_before {
+        Some(uuid_before) => idcache.get_username_by_uuid(&uuid_before).await.ok(),
+        None => None,
+    };
+
+    let msg = update_whitelist(server, idcache, name_before.as_deref(), mcname)?;
+
     if msg.body.trim() == "That player does not exist" {
         command
             .followup_err(&ctx.http, "That Minecraft player name does not not exist.")
@@ -81,7 +129,21 @@ pub async fn run(
         return Ok(());
     }
 
-    db.set_user(command.user.id, &uuid).await?;
+    // Whitelist entries on other servers are moved to the new name as well.
+    for other in db.get_user_servers(guild.id, command.user.id).await? {
+        if other == server.name() {
+            continue;
+        }
+        if let Err(err) = update_whitelist(rc.get(&other)?, idcache, name_before.as_deref(), mcname)
+        {
+            log::error!("Updating whitelist on server {other} failed: {err}");
+        }
+    }
+
+    db.set_user(guild.id, command.user.id, &uuid, mcname)
+        .await?;
+    db.add_user_server(guild.id, command.user.id, server.name())
+        .await?;
 
     command
         .followup(
@@ -98,3 +160,117 @@ pub async fn run(
 
     Ok(())
 }
+
+pub async fn autocomplete(ctx: &Context, i: &AutocompleteInteraction, rc: &Servers) -> Result<()> {
+    if let Some(server) = i
+        .data
+        .options
+        .iter()
+        .find(|o| o.name == "server" && o.focused)
+    {
+        helpers::autocomplete_servers(ctx, i, rc, server).await?;
+    }
+
+    Ok(())
+}
+
+/// Removes the previous name, if any, from the whitelist of the
+/// given server and adds the new one.
+fn update_whitelist(
+    rc: &Rcon,
+    idcache: &IdCache,
+    name_before: Option<&str>,
+    mcname: &str,
+) -> Result<Message> {
+    let mut rc = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    if let Some(name_before) = name_before {
+        rc.cmd(&idcache.provider().whitelist_command("remove", name_before))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+
+    rc.cmd(&idcache.provider().whitelist_command("add", mcname))
+        .map_err(|e| anyhow::anyhow!(e.to_string()))
+}
+
+/// Lets the player prove that they own the given account by
+/// entering a code in game.
+///
+/// Returns `false` if the verification failed, in which case
+/// the user has already been notified.
+async fn verify(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    rc: &Rcon,
+    idcache: &IdCache,
+    cfg: &conf::Verification,
+    mcname: &str,
+    shutdown: &Shutdown,
+) -> Result<bool> {
+    let code = verification::generate_code(cfg.code_length);
+
+    let mut temp_whitelisted = false;
+    if cfg.temp_whitelist {
+        let mut rc = rc
+            .get_conn()
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+        let msg = rc
+            .cmd(&idcache.provider().whitelist_command("add", mcname))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+        match msg.body.trim() {
+            "That player does not exist" => {
+                command
+                    .followup_err(&ctx.http, "That Minecraft player name does not not exist.")
+                    .await?;
+                return Ok(false);
+            }
+            "Player is already whitelisted" => {}
+            _ => temp_whitelisted = true,
+        }
+    }
+
+    command
+        .followup(
+            &ctx.http,
+            format!(
+                concat!(
+                    "To verify that {} is your account, join the server and type `{}` ",
+                    "into the chat within {} minutes. ⏳"
+                ),
+                mcname,
+                code,
+                cfg.timeout_secs.div_ceil(60)
+            ),
+        )
+        .await?;
+
+    let res = shutdown
+        .interruptible(verification::verify(cfg, rc, mcname, &code))
+        .await
+        .and_then(|res| res);
+
+    if temp_whitelisted && !matches!(res, Ok(true)) {
+        let mut rc = rc
+            .get_conn()
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+        rc.cmd(&idcache.provider().whitelist_command("remove", mcname))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+
+    if !res? {
+        command
+            .followup_err(
+                &ctx.http,
+                "The verification code has not been entered in time. Please try again.",
+            )
+            .await?;
+        return Ok(false);
+    }
+
+    Ok(true)
+}
@@ -1,2 +1,16 @@
+use serenity::builder::CreateApplicationCommands;
+
+pub mod admin;
 pub mod bind;
 pub mod region;
+pub mod unbind;
+pub mod whoami;
+
+pub fn register(commands: &mut CreateApplicationCommands) -> &mut CreateApplicationCommands {
+    commands
+        .create_application_command(|command| region::register(command))
+        .create_application_command(|command| bind::register(command))
+        .create_application_command(|command| unbind::register(command))
+        .create_application_command(|command| whoami::register(command))
+        .create_application_command(|command| admin::register(command))
+}
@@ -1,10 +1,15 @@
+use crate::conf;
 use crate::db::Database;
-use crate::helpers::{FollowUpHelper, OptionsHelper};
-use crate::idcache::get_username_by_uuid;
-use crate::mc::{Conn, Rcon};
+use crate::helpers::{self, FollowUpHelper, OptionsHelper};
+use crate::idcache::IdCache;
+use crate::mc::{check_err, Servers};
 use crate::models::{Perimeter, Point, Region};
-use anyhow::{bail, Result};
-use minecraft_client_rs::Message;
+use crate::plots;
+use crate::review;
+use crate::selection;
+use crate::shutdown::Shutdown;
+use crate::wizard;
+use anyhow::Result;
 use serenity::builder::{CreateApplicationCommand, CreateEmbed};
 use serenity::futures::future::join_all;
 use serenity::json::json;
@@ -18,11 +23,8 @@ use serenity::model::prelude::interaction::InteractionResponseType;
 use serenity::model::user::User;
 use serenity::prelude::Context;
 use serenity::utils::Color;
-use std::error::Error;
 use std::time::Duration;
 
-const ERR_PREFIX: &str = "§c";
-
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
     command
         .name("region")
@@ -33,6 +35,12 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
             o.name("list")
                 .description("List your plots.")
                 .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("server")
+                        .description("Only list plots on this server.")
+                        .kind(CommandOptionType::String)
+                        .set_autocomplete(true)
+                })
         })
         // ----------------------------------
         // create sub command
@@ -72,6 +80,33 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                         .add_string_choice("Nether", "nether")
                         .add_string_choice("The End", "the_end")
                 })
+                .create_sub_option(|so| {
+                    so.name("server")
+                        .description("The server to create the plot on")
+                        .kind(CommandOptionType::String)
+                        .set_autocomplete(true)
+                })
+        })
+        // ----------------------------------
+        // create-from-selection sub command
+        .create_option(|o| {
+            o.name("create-from-selection")
+                .description("Create a new personal region around your current position")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("radius")
+                        .description("The radius of the square around you")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .max_int_value(500)
+                        .required(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("server")
+                        .description("The server you are online on")
+                        .kind(CommandOptionType::String)
+                        .set_autocomplete(true)
+                })
         })
         // ----------------------------------
         // redefine sub command
@@ -112,7 +147,7 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 })
                 .create_sub_option(|so| {
                     so.name("world")
-                        .description("The world to create the plot in")
+                        .description("The world the plot is in, if it has been stored wrongly")
                         .kind(CommandOptionType::String)
                         .add_string_choice("Overworld", "world")
                         .add_string_choice("Nether", "nether")
@@ -120,6 +155,44 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 })
         })
         // ----------------------------------
+        // subregion sub command
+        .create_option(|o| {
+            o.name("subregion")
+                .description("Create a region inside one of your plots with its own members")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("plotname")
+                        .description("The name of the plot to create the subregion in.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("pos1-x")
+                        .description("The X coordinate of the first corner position.")
+                        .kind(CommandOptionType::Integer)
+                        .required(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("pos1-z")
+                        .description("The Z coordinate of the first corner position.")
+                        .kind(CommandOptionType::Integer)
+                        .required(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("pos2-x")
+                        .description("The X coordinate of the second corner position.")
+                        .kind(CommandOptionType::Integer)
+                        .required(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("pos2-z")
+                        .description("The Z coordinate of the second corner position.")
+                        .kind(CommandOptionType::Integer)
+                        .required(true)
+                })
+        })
+        // ----------------------------------
         // member sub command group
         .create_option(|o| {
             o.name("members")
@@ -165,6 +238,83 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                 })
         })
         // ----------------------------------
+        // info sub command
+        .create_option(|o| {
+            o.name("info")
+                .description("Show the details of a plot.")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("plotname")
+                        .description("The name of the plot.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+        })
+        // ----------------------------------
+        // flag sub command group
+        .create_option(|o| {
+            o.name("flag")
+                .description("Commands to manage the flags of your plot.")
+                .kind(CommandOptionType::SubCommandGroup)
+                .create_sub_option(|so| {
+                    so.name("set")
+                        .description("Set a flag on your plot.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("plotname")
+                                .description("The name of the plot.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("flag")
+                                .description("The name of the flag.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("value")
+                                .description("The value of the flag.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                        })
+                })
+                .create_sub_option(|so| {
+                    so.name("clear")
+                        .description("Remove a flag from your plot.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("plotname")
+                                .description("The name of the plot.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                        .create_sub_option(|sso| {
+                            sso.name("flag")
+                                .description("The name of the flag.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                })
+                .create_sub_option(|so| {
+                    so.name("list")
+                        .description("List the flags of your plot.")
+                        .kind(CommandOptionType::SubCommand)
+                        .create_sub_option(|sso| {
+                            sso.name("plotname")
+                                .description("The name of the plot.")
+                                .kind(CommandOptionType::String)
+                                .required(true)
+                                .set_autocomplete(true)
+                        })
+                })
+        })
+        // ----------------------------------
         // delete sub command
         .create_option(|o| {
             o.name("delete")
@@ -178,15 +328,94 @@ pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicatio
                         .set_autocomplete(true)
                 })
         })
+        // ----------------------------------
+        // restore sub command
+        .create_option(|o| {
+            o.name("restore")
+                .description("Restore one of your deleted or expired plots")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("plotname")
+                        .description("The name of your deleted or expired plot.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+        })
+        // ----------------------------------
+        // revisions sub command
+        .create_option(|o| {
+            o.name("revisions")
+                .description("List the previous perimeters of your plot")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("plotname")
+                        .description("The name of your plot.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+        })
+        // ----------------------------------
+        // revert sub command
+        .create_option(|o| {
+            o.name("revert")
+                .description("Change the perimeter of your plot back to a previous one")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("plotname")
+                        .description("The name of your plot.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("revision")
+                        .description("The number of the revision, see `/region revisions`.")
+                        .kind(CommandOptionType::Integer)
+                        .min_int_value(1)
+                        .required(true)
+                })
+        })
+        // ----------------------------------
+        // rename sub command
+        .create_option(|o| {
+            o.name("rename")
+                .description("Give your plot a name of your choice")
+                .kind(CommandOptionType::SubCommand)
+                .create_sub_option(|so| {
+                    so.name("plotname")
+                        .description("The name of your plot.")
+                        .kind(CommandOptionType::String)
+                        .required(true)
+                        .set_autocomplete(true)
+                })
+                .create_sub_option(|so| {
+                    so.name("name")
+                        .description("The new name. Leave empty to remove the display name.")
+                        .kind(CommandOptionType::String)
+                        .max_length(64)
+                })
+                .create_sub_option(|so| {
+                    so.name("region-id")
+                        .description("Rename the WorldGuard region itself instead of only setting a display name.")
+                        .kind(CommandOptionType::Boolean)
+                })
+        })
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn run(
     ctx: &Context,
     command: &ApplicationCommandInteraction,
+    cfg: &conf::Config,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+    shutdown: &Shutdown,
 ) -> Result<()> {
-    let res = db.get_user_by_id(command.user.id).await?;
+    let res = db.get_user_by_id(guild.id, command.user.id).await?;
     if res.is_none() {
         command.followup_err(&ctx.http,
             "You have not registered a Minecraft username. Please use the `/bind` command to bind your Discord account to your Minecrfat username.")
@@ -194,7 +423,10 @@ pub async fn run(
         return Ok(());
     }
 
-    let username = get_username_by_uuid(&res.unwrap()).await?.to_lowercase();
+    let username = idcache
+        .get_username_by_uuid(&res.unwrap())
+        .await?
+        .to_lowercase();
 
     let options = &command.data.options;
     let subcmd = options
@@ -202,11 +434,21 @@ pub async fn run(
         .ok_or_else(|| anyhow::anyhow!("Response does not contain any sub command option."))?;
 
     match subcmd.name.as_str() {
-        "list" => list(ctx, command, db).await,
-        "create" => create(ctx, command, subcmd, &username, db, rc).await,
-        "redefine" => redefine(ctx, command, subcmd, db, rc).await,
-        "members" => members(ctx, command, subcmd, db, rc).await,
-        "delete" => delete(ctx, command, subcmd, db, rc).await,
+        "list" => list(ctx, command, subcmd, db, rc, guild).await,
+        "create" => create(ctx, command, subcmd, &username, db, rc, guild, shutdown).await,
+        "create-from-selection" => {
+            create_from_selection(ctx, command, subcmd, &username, db, rc, guild, shutdown).await
+        }
+        "redefine" => redefine(ctx, command, subcmd, db, rc, guild).await,
+        "subregion" => subregion(ctx, command, subcmd, &username, db, rc, guild).await,
+        "members" => members(ctx, command, subcmd, db, rc, idcache, guild).await,
+        "info" => info(ctx, command, subcmd, db, rc, idcache, guild).await,
+        "flag" => flag(ctx, command, subcmd, cfg, db, rc, guild).await,
+        "delete" => delete(ctx, command, subcmd, cfg, db, rc, guild, shutdown).await,
+        "revisions" => revisions(ctx, command, subcmd, db, guild).await,
+        "revert" => revert(ctx, command, subcmd, db, rc, guild).await,
+        "rename" => rename(ctx, command, subcmd, &username, db, rc, idcache, guild).await,
+        "restore" => restore(ctx, command, subcmd, &username, db, rc, idcache, guild).await,
         _ => Err(anyhow::anyhow!("Unregistered sub command")),
     }
 }
@@ -235,22 +477,40 @@ async fn get_user(ctx: &Context, id: u64) -> Result<User> {
     }
 }
 
-pub async fn autocomplete(ctx: &Context, i: &AutocompleteInteraction, db: &Database) -> Result<()> {
+pub async fn autocomplete(
+    ctx: &Context,
+    i: &AutocompleteInteraction,
+    cfg: &conf::Config,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    if let Some(server) = find_option_deep(i, "server") {
+        helpers::autocomplete_servers(ctx, i, rc, &server).await?;
+    }
+
     if let Some(plotname) = find_option_deep(i, "plotname") {
-        let plots = db
-            .get_user_plots(i.user.id)
-            .await?
+        let plots = match i.data.options.first().map(|o| o.name.as_str()) {
+            Some("restore") => db.get_removed_user_plots(guild.id, i.user.id).await?,
+            _ => db.get_user_plots(guild.id, i.user.id).await?,
+        };
+
+        let plots = plots
             .iter()
             .filter(|p| {
                 plotname
                     .value
                     .as_ref()
                     .and_then(|v| v.as_str())
-                    .is_some_and(|v| p.name.starts_with(v))
+                    .is_some_and(|v| p.name.starts_with(v) || p.label().starts_with(v))
             })
             .map(|p| {
                 json!({
-                    "name": p.name,
+                    "name": match &p.display_name {
+                        Some(label) => format!("{label} ({})", p.name),
+                        None => p.name.clone(),
+                    },
                     "value": p.name
                 })
             })
@@ -260,16 +520,38 @@ pub async fn autocomplete(ctx: &Context, i: &AutocompleteInteraction, db: &Datab
             .await?;
     }
 
+    if let Some(flag) = find_option_deep(i, "flag") {
+        let value = flag
+            .value
+            .as_ref()
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+
+        let flags = cfg
+            .flags
+            .iter()
+            .filter(|f| f.name.starts_with(value))
+            .map(|f| json!({ "name": f.name, "value": f.name }))
+            .collect();
+
+        i.create_autocomplete_response(&ctx.http, |r| r.set_choices(flags))
+            .await?;
+    }
+
     if let Some(username) = find_option_deep(i, "username") {
         let res = db.list_users().await?;
 
-        let users = join_all(res.iter().map(|u| async {
-            let uname = get_user(ctx, u.discord_id)
-                .await
-                .map(|u| u.name)
-                .unwrap_or_else(|_| u.discord_id.to_string());
-            (u.clone(), uname)
-        }))
+        let users = join_all(
+            res.iter()
+                .filter(|u| u.guild_id == guild.id)
+                .map(|u| async {
+                    let uname = get_user(ctx, u.discord_id)
+                        .await
+                        .map(|u| u.name)
+                        .unwrap_or_else(|_| u.discord_id.to_string());
+                    (u.clone(), uname)
+                }),
+        )
         .await;
 
         let usernames = users
@@ -283,7 +565,8 @@ pub async fn autocomplete(ctx: &Context, i: &AutocompleteInteraction, db: &Datab
                         .is_some_and(|v| uname.starts_with(v))
             })
             .map(|(u, uname)| async {
-                minecraft_uuid::get_username_by_uuid(&u.minecraft_uid)
+                idcache
+                    .get_username_by_uuid(&u.minecraft_uid)
                     .await
                     .ok()
                     .map(|mc_uname| {
@@ -312,12 +595,37 @@ pub async fn autocomplete(ctx: &Context, i: &AutocompleteInteraction, db: &Datab
 
 // ---- SUB COMMAND HANDLERS ----
 
-async fn list(ctx: &Context, command: &ApplicationCommandInteraction, db: &Database) -> Result<()> {
-    let plots = db
-        .get_user_plots(command.user.id)
-        .await?
+async fn list(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let server = subcmd.get_option_by_name("server").and_then(|v| v.as_str());
+    let multiple_servers = rc.names().count() > 1;
+
+    let plots = db.get_user_plots(guild.id, command.user.id).await?;
+
+    // Subregions are listed below their parent plot.
+    let plots = plots
         .iter()
-        .map(|p| format!("  ▫️ {}", p))
+        .filter(|p| p.parent.is_none() && server.is_none_or(|s| p.server == s))
+        .flat_map(|p| {
+            let mut line = format!("  ▫️ {}", p);
+            if multiple_servers {
+                line.push_str(&format!(" on {}", p.server));
+            }
+            if p.pending {
+                line.push_str(" *(waiting for approval)*");
+            }
+            let subregions = plots
+                .iter()
+                .filter(|s| s.parent.as_ref() == Some(&p.name))
+                .map(|s| format!("      └ {s}"));
+            std::iter::once(line).chain(subregions)
+        })
         .collect::<Vec<_>>()
         .join("\n");
 
@@ -334,20 +642,18 @@ async fn list(ctx: &Context, command: &ApplicationCommandInteraction, db: &Datab
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn create(
     ctx: &Context,
     command: &ApplicationCommandInteraction,
     subcmd: &CommandDataOption,
     username: &str,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+    shutdown: &Shutdown,
 ) -> Result<()> {
-    let plot_id = db
-        .get_plot_user_id(command.user.id)
-        .await?
-        .unwrap_or_default();
-
-    let plot_name = format!("{}_plot_{}", username.replace('_', ""), plot_id + 1);
+    let server = subcmd.get_option_by_name("server").and_then(|v| v.as_str());
 
     let world = subcmd
         .get_option_by_name("world")
@@ -369,29 +675,120 @@ async fn create(
         ),
     );
 
-    let collisions = find_collisions(db, command.user.id.into(), &perimeter).await?;
-    if !collisions.is_empty() {
-        anyhow::bail!(
-            "The perimeter of your defined plot would collide with {} other plot{}!",
-            collisions.len(),
-            if collisions.len() > 1 { "s" } else { "" }
-        );
-    }
+    let Some(perimeter) = wizard::run(
+        ctx,
+        command,
+        db,
+        rc,
+        command.user.id.into(),
+        server,
+        world,
+        perimeter,
+        shutdown,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
 
-    let region = Region {
-        owner: command.user.id.into(),
-        name: plot_name.clone(),
+    let region = plots::create(
+        db,
+        rc,
+        guild,
+        command.user.id.into(),
+        username,
+        server,
+        world,
         perimeter,
+    )
+    .await?;
+
+    reply_created(ctx, command, db, guild, &region).await
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn create_from_selection(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    username: &str,
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+    shutdown: &Shutdown,
+) -> Result<()> {
+    let server = subcmd.get_option_by_name("server").and_then(|v| v.as_str());
+    let radius = subcmd
+        .get_required_option_by_name("radius")?
+        .as_i64()
+        .ok_or_else(|| anyhow::anyhow!("Radius value is not an integer"))?;
+
+    let (world, perimeter) = {
+        let rcon = rc
+            .get_or_default(server)
+            .map_err(|e| plots::Rejected(e.to_string()))?;
+        selection::read_square(rcon, username, radius)?
     };
 
-    db.inc_plot_user_id(command.user.id).await?;
-    create_plot(rc, &region, username, world)?;
-    db.add_plot(&region).await?;
+    let Some(perimeter) = wizard::run(
+        ctx,
+        command,
+        db,
+        rc,
+        command.user.id.into(),
+        server,
+        &world,
+        perimeter,
+        shutdown,
+    )
+    .await?
+    else {
+        return Ok(());
+    };
+
+    let region = plots::create(
+        db,
+        rc,
+        guild,
+        command.user.id.into(),
+        username,
+        server,
+        &world,
+        perimeter,
+    )
+    .await?;
+
+    reply_created(ctx, command, db, guild, &region).await
+}
+
+/// Tells the user that the plot has been created, or posts it for
+/// review if it needs approval.
+async fn reply_created(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    db: &Database,
+    guild: &conf::GuildSettings,
+    region: &Region,
+) -> Result<()> {
+    if region.pending {
+        review::request(&ctx.http, db, guild, region).await?;
+        command
+            .followup(
+                &ctx.http,
+                format!(
+                    "Your plot `{}` has to be approved by the staff first. \
+                     You will get a DM once it has been reviewed. ⏳",
+                    region.name
+                ),
+            )
+            .await?;
+        return Ok(());
+    }
 
     command
         .followup(
             &ctx.http,
-            format!("Your plot `{plot_name}` has been created! 🎉"),
+            format!("Your plot `{}` has been created! 🎉", region.name),
         )
         .await?;
 
@@ -403,7 +800,8 @@ async fn redefine(
     command: &ApplicationCommandInteraction,
     subcmd: &CommandDataOption,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
 ) -> Result<()> {
     let plot_name = &subcmd
         .get_required_option_by_name("plotname")?
@@ -417,16 +815,15 @@ async fn redefine(
             v.as_str()
                 .ok_or_else(|| anyhow::anyhow!("World value is not a string"))
         })
-        .transpose()?
-        .unwrap_or("world");
+        .transpose()?;
 
-    let region = db.get_plot_by_name(plot_name).await?;
-    if region.is_none() || region.unwrap().owner != u64::from(command.user.id) {
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), plot_name).await?
+    else {
         command
             .followup_err(&ctx.http, "You can not update this plot.")
             .await?;
         return Ok(());
-    }
+    };
 
     let perimeter = Perimeter(
         Point(
@@ -439,28 +836,63 @@ async fn redefine(
         ),
     );
 
-    let collisions = find_collisions(db, command.user.id.into(), &perimeter).await?;
-    if !collisions.is_empty() {
-        anyhow::bail!(
-            "The perimeter of your defined plot would collide with {} other plot{}!",
-            collisions.len(),
-            if collisions.len() > 1 { "s" } else { "" }
-        );
-    }
+    plots::redefine(db, rc, guild, region, world, perimeter).await?;
 
-    let region = Region {
-        owner: command.user.id.into(),
-        name: plot_name.clone(),
-        perimeter,
+    command
+        .followup(
+            &ctx.http,
+            format!("The perimeter of your plot `{plot_name}` has been updated! 🎉"),
+        )
+        .await?;
+
+    Ok(())
+}
+
+async fn subregion(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    username: &str,
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
+
+    let Some(parent) = plots::find_owned(db, guild, command.user.id.into(), &plot_name).await?
+    else {
+        command
+            .followup_err(&ctx.http, "You can not update this plot.")
+            .await?;
+        return Ok(());
     };
 
-    update_plot(rc, &region, world)?;
-    db.update_plot(&region).await?;
+    let perimeter = Perimeter(
+        Point(
+            get_pos_option(subcmd, "pos1-x")?,
+            get_pos_option(subcmd, "pos1-z")?,
+        ),
+        Point(
+            get_pos_option(subcmd, "pos2-x")?,
+            get_pos_option(subcmd, "pos2-z")?,
+        ),
+    );
+
+    let region = plots::create_subregion(db, rc, &parent, username, perimeter).await?;
 
     command
         .followup(
             &ctx.http,
-            format!("The perimeter of your plot `{plot_name}` has been updated! 🎉"),
+            format!(
+                "The subregion `{}` has been created in your plot `{}`! 🎉 \
+                 Add members to it with `/region members add`.",
+                region.name,
+                parent.label()
+            ),
         )
         .await?;
 
@@ -472,7 +904,9 @@ async fn members(
     command: &ApplicationCommandInteraction,
     subcmd: &CommandDataOption,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
 ) -> Result<()> {
     let subcmd = subcmd
         .options
@@ -480,8 +914,8 @@ async fn members(
         .ok_or_else(|| anyhow::anyhow!("Response does not contain any sub command option."))?;
 
     match subcmd.name.as_str() {
-        "add" => members_add(ctx, command, subcmd, db, rc).await,
-        "remove" => members_remove(ctx, command, subcmd, db, rc).await,
+        "add" => members_add(ctx, command, subcmd, db, rc, idcache, guild).await,
+        "remove" => members_remove(ctx, command, subcmd, db, rc, idcache, guild).await,
         _ => Err(anyhow::anyhow!("Unregistered sub-sub command")),
     }
 }
@@ -491,7 +925,9 @@ async fn members_add(
     command: &ApplicationCommandInteraction,
     subcmd: &CommandDataOption,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
 ) -> Result<()> {
     let plotname = subcmd
         .get_required_option_by_name("plotname")?
@@ -504,28 +940,40 @@ async fn members_add(
         .as_str()
         .ok_or_else(|| anyhow::anyhow!("Value is not a string"))?;
 
-    let region = db.get_plot_by_name(&plotname).await?;
-
-    if region.is_none() || region.unwrap().owner != u64::from(command.user.id) {
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), &plotname).await?
+    else {
         command
             .followup_err(&ctx.http, "You can not alter the members of this plot.")
             .await?;
         return Ok(());
-    }
+    };
+    plots::check_active(&region)?;
+
+    // Resolved first, so that WorldGuard and the database do not
+    // disagree about the members if the name is unknown.
+    let uuid = idcache.get_uuid_by_username(membername).await?;
 
     {
         let mut conn = rc
+            .get(&region.server)?
             .get_conn()
-            .map_err(|e| anyhow::anyhow!("RCON connection failed: {}", e.to_string()))?;
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
 
-        // TODO: Make world value configurable
-        check_err(conn.cmd(&format!("rg addmember -w world {plotname} {membername}")))?;
+        check_err(conn.cmd(&format!(
+            "rg addmember -w {} {} {membername}",
+            region.world, region.name
+        )))?;
     }
 
+    db.add_plot_member(&region.name, &uuid).await?;
+
     command
         .followup(
             &ctx.http,
-            format!("Member {membername} has been added to plot {plotname}! 🎉"),
+            format!(
+                "Member {membername} has been added to plot {}! 🎉",
+                region.label()
+            ),
         )
         .await?;
 
@@ -537,7 +985,9 @@ async fn members_remove(
     command: &ApplicationCommandInteraction,
     subcmd: &CommandDataOption,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
 ) -> Result<()> {
     let plotname = subcmd
         .get_required_option_by_name("plotname")?
@@ -550,42 +1000,54 @@ async fn members_remove(
         .as_str()
         .ok_or_else(|| anyhow::anyhow!("Value is not a string"))?;
 
-    let region = db.get_plot_by_name(&plotname).await?;
-
-    if region.is_none() || region.unwrap().owner != u64::from(command.user.id) {
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), &plotname).await?
+    else {
         command
             .followup_err(&ctx.http, "You can not alter the members of this plot.")
             .await?;
         return Ok(());
-    }
+    };
+    plots::check_active(&region)?;
+
+    let uuid = idcache.get_uuid_by_username(membername).await?;
 
     {
         let mut conn = rc
+            .get(&region.server)?
             .get_conn()
-            .map_err(|e| anyhow::anyhow!("RCON connection failed: {}", e.to_string()))?;
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
 
-        // TODO: Make world value configurable
         check_err(conn.cmd(&format!(
-            "rg removemember -w world  {plotname} {membername}"
+            "rg removemember -w {} {} {membername}",
+            region.world, region.name
         )))?;
     }
 
+    db.remove_plot_member(&region.name, &uuid).await?;
+
     command
         .followup(
             &ctx.http,
-            format!("Member {membername} has been removed from plot {plotname}!"),
+            format!(
+                "Member {membername} has been removed from plot {}!",
+                region.label()
+            ),
         )
         .await?;
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn delete(
     ctx: &Context,
     command: &ApplicationCommandInteraction,
     subcmd: &CommandDataOption,
+    cfg: &conf::Config,
     db: &Database,
-    rc: &Rcon,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+    shutdown: &Shutdown,
 ) -> Result<()> {
     let plot_name = &subcmd
         .get_required_option_by_name("plotname")?
@@ -593,13 +1055,13 @@ async fn delete(
         .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
         .to_lowercase();
 
-    let region = db.get_plot_by_name(plot_name).await?;
-    if region.is_none() || region.unwrap().owner != u64::from(command.user.id) {
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), plot_name).await?
+    else {
         command
             .followup_err(&ctx.http, "You can not delete this plot.")
             .await?;
         return Ok(());
-    }
+    };
 
     let ok_id = xid::new().to_string();
     let cancel_id = xid::new().to_string();
@@ -630,10 +1092,12 @@ async fn delete(
         })
         .await?;
 
-    let interaction = m
-        .await_component_interaction(ctx)
-        .timeout(Duration::from_secs(60))
-        .await
+    let interaction = shutdown
+        .interruptible(
+            m.await_component_interaction(ctx)
+                .timeout(Duration::from_secs(60)),
+        )
+        .await?
         .ok_or_else(|| anyhow::anyhow!("Timed out."))?;
 
     if interaction.data.custom_id == cancel_id {
@@ -653,16 +1117,7 @@ async fn delete(
         return Ok(());
     }
 
-    {
-        let mut conn = rc
-            .get_conn()
-            .map_err(|e| anyhow::anyhow!("RCON connection failed: {}", e.to_string()))?;
-
-        // TODO: Make world configurable
-        check_err(conn.cmd(&format!("rg delete -w world {plot_name}")))?;
-    }
-
-    db.delete_plot(plot_name).await?;
+    plots::delete(db, rc, &region).await?;
 
     interaction
         .create_interaction_response(&ctx.http, |r| {
@@ -671,7 +1126,11 @@ async fn delete(
                     d.add_embed(
                         CreateEmbed::default()
                             .color(Color::FOOYOO)
-                            .description("The plot has been deleted.")
+                            .description(format!(
+                                "The plot has been deleted. You can restore it with \
+                                 `/region restore` within the next {} days.",
+                                cfg.trash.retention_days
+                            ))
                             .to_owned(),
                     )
                     .components(|c| c)
@@ -682,67 +1141,455 @@ async fn delete(
     Ok(())
 }
 
-// ---- HELPERS ----
+#[allow(clippy::too_many_arguments)]
+async fn restore(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    username: &str,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
 
-fn get_pos_option(subcmd: &CommandDataOption, name: &str) -> Result<i64> {
-    let i = subcmd
-        .get_required_option_by_name(name)?
-        .as_i64()
-        .ok_or_else(|| anyhow::anyhow!("Value is not of type i64"))?;
-    Ok(i)
+    let Some(region) = db
+        .get_removed_user_plots(guild.id, command.user.id)
+        .await?
+        .into_iter()
+        .find(|p| p.name == plot_name)
+    else {
+        command
+            .followup_err(
+                &ctx.http,
+                "You have no deleted or expired plot with this name.",
+            )
+            .await?;
+        return Ok(());
+    };
+
+    plots::restore(
+        db,
+        rc,
+        idcache,
+        guild,
+        &region,
+        command.user.id.into(),
+        username,
+    )
+    .await?;
+
+    command
+        .followup(
+            &ctx.http,
+            format!("Your plot `{}` has been restored! 🎉", region.name),
+        )
+        .await?;
+
+    Ok(())
 }
 
-fn create_plot(rc: &Rcon, region: &Region, user_name: &str, world: &str) -> Result<()> {
-    let mut conn = rc
-        .get_conn()
-        .map_err(|e| anyhow::anyhow!("RCON connection failed: {}", e.to_string()))?;
+async fn revisions(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
 
-    select_perimeter(&mut conn, &region.perimeter, world)?;
-    check_err(conn.cmd(&format!("region create {} {}", region.name, user_name)))?;
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), &plot_name).await?
+    else {
+        command
+            .followup_err(&ctx.http, "You can not view the revisions of this plot.")
+            .await?;
+        return Ok(());
+    };
+
+    let revisions = db
+        .get_plot_revisions(&region.name)
+        .await?
+        .iter()
+        .map(|r| {
+            let p = &r.perimeter;
+            format!(
+                "  ▫️ `#{}` `{}, {}` → `{}, {}` in {} ({}m²), replaced <t:{}:R>",
+                r.number,
+                p.0 .0,
+                p.0 .1,
+                p.1 .0,
+                p.1 .1,
+                r.world,
+                p.size(),
+                r.created_at
+            )
+        })
+        .collect::<Vec<_>>();
+
+    let description = match revisions.is_empty() {
+        true => format!("The perimeter of `{}` has never been changed.", region.name),
+        false => format!(
+            "These are the previous perimeters of `{}`:\n\n{}",
+            region.name,
+            revisions.join("\n")
+        ),
+    };
+
+    command
+        .followup_embed(
+            &ctx.http,
+            CreateEmbed::default()
+                .color(Color::BLURPLE)
+                .description(description)
+                .to_owned(),
+        )
+        .await?;
 
     Ok(())
 }
 
-fn update_plot(rc: &Rcon, region: &Region, world: &str) -> Result<()> {
-    let mut conn = rc
-        .get_conn()
-        .map_err(|e| anyhow::anyhow!("RCON connection failed: {}", e.to_string()))?;
+async fn revert(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
+
+    let number = subcmd
+        .get_required_option_by_name("revision")?
+        .as_u64()
+        .ok_or_else(|| anyhow::anyhow!("Revision value is not a number"))?;
 
-    select_perimeter(&mut conn, &region.perimeter, world)?;
-    check_err(conn.cmd(&format!("rg update {}", region.name)))?;
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), &plot_name).await?
+    else {
+        command
+            .followup_err(&ctx.http, "You can not update this plot.")
+            .await?;
+        return Ok(());
+    };
+
+    let Some(revision) = db
+        .get_plot_revisions(&region.name)
+        .await?
+        .into_iter()
+        .find(|r| u64::from(r.number) == number)
+    else {
+        command
+            .followup_err(&ctx.http, "There is no revision with this number.")
+            .await?;
+        return Ok(());
+    };
+
+    // Reverting is a change itself, so it can be undone as well.
+    plots::redefine(
+        db,
+        rc,
+        guild,
+        region,
+        Some(&revision.world),
+        revision.perimeter,
+    )
+    .await?;
+
+    command
+        .followup(
+            &ctx.http,
+            format!(
+                "The perimeter of your plot `{plot_name}` has been reverted to revision #{number}! ⏪"
+            ),
+        )
+        .await?;
 
     Ok(())
 }
 
-fn select_perimeter(conn: &mut Conn, perimeter: &Perimeter, world: &str) -> Result<()> {
-    // TODO: Make configurable or whatever.
-    check_err(conn.cmd(&format!("//world {world}")))?;
-    check_err(conn.cmd(&format!("//pos1 {},0,{}", perimeter.0 .0, perimeter.0 .1)))?;
-    check_err(conn.cmd(&format!("//pos2 {},0,{}", perimeter.1 .0, perimeter.1 .1)))?;
-    check_err(conn.cmd("//expand vert"))?;
+#[allow(clippy::too_many_arguments)]
+async fn rename(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    username: &str,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
+
+    let name = subcmd
+        .get_option_by_name("name")
+        .and_then(|v| v.as_str())
+        .map(str::trim)
+        .filter(|n| !n.is_empty());
+
+    let region_id = subcmd
+        .get_option_by_name("region-id")
+        .and_then(|v| v.as_bool())
+        .unwrap_or_default();
+
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), &plot_name).await?
+    else {
+        command
+            .followup_err(&ctx.http, "You can not update this plot.")
+            .await?;
+        return Ok(());
+    };
+
+    let message = match (name, region_id) {
+        (Some(name), true) => {
+            let renamed = plots::rename(db, rc, idcache, &region, name, username).await?;
+            format!(
+                "The region of your plot is now called `{}`! 🏷️",
+                renamed.name
+            )
+        }
+        (None, true) => {
+            command
+                .followup_err(&ctx.http, "Please enter the new region ID.")
+                .await?;
+            return Ok(());
+        }
+        (Some(name), false) => {
+            plots::set_display_name(db, &region, Some(name)).await?;
+            format!(
+                "Your plot `{}` is now called `{}`! 🏷️",
+                region.name,
+                name.to_lowercase()
+            )
+        }
+        (None, false) => {
+            plots::set_display_name(db, &region, None).await?;
+            format!("The display name of `{}` has been removed.", region.name)
+        }
+    };
+
+    command.followup(&ctx.http, message).await?;
+
     Ok(())
 }
 
-fn check_err(res: Result<Message, Box<dyn Error>>) -> Result<Message> {
-    let msg = res.map_err(|e| anyhow::anyhow!(e.to_string()))?;
-    if msg.body.starts_with(ERR_PREFIX) {
-        bail!(msg.body);
+async fn info(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
+
+    let Some(region) = db.find_plot(guild.id, &plot_name).await? else {
+        command
+            .followup_err(&ctx.http, "There is no plot with this name.")
+            .await?;
+        return Ok(());
+    };
+
+    let mut members = Vec::new();
+    for uuid in db.get_plot_members(&region.name).await? {
+        let name = idcache
+            .get_username_by_uuid(&uuid)
+            .await
+            .unwrap_or_else(|_| format!("{uuid:#}"));
+        members.push(name);
+    }
+
+    let flags = db
+        .get_plot_flags(&region.name)
+        .await?
+        .iter()
+        .map(|(flag, value)| format!("`{flag}`: {value}"))
+        .collect::<Vec<_>>();
+
+    let mut embed = CreateEmbed::default();
+    embed
+        .color(Color::BLURPLE)
+        .title(region.label())
+        .field(
+            "Owner",
+            region
+                .owner
+                .map_or("*none*".into(), |id| format!("<@{id}>")),
+            true,
+        )
+        .field("World", &region.world, true);
+
+    if region.display_name.is_some() {
+        embed.field("Region ID", &region.name, true);
+    }
+
+    if let Some(parent) = &region.parent {
+        let label = db
+            .get_plot_by_name(parent)
+            .await?
+            .map_or(parent.clone(), |p| p.label().to_owned());
+        embed.field("Subregion of", format!("`{label}`"), true);
+    }
+
+    if region.pending {
+        embed.field("Status", "Waiting for approval", true);
+    }
+
+    if rc.names().count() > 1 {
+        embed.field("Server", &region.server, true);
+    }
+
+    let p = &region.perimeter;
+    embed
+        .field(
+            "Perimeter",
+            format!("`{}, {}` → `{}, {}`", p.0 .0, p.0 .1, p.1 .0, p.1 .1),
+            true,
+        )
+        .field("Area", format!("{}m²", p.size()), true)
+        .field("Members", or_none(members.join(", ")), false)
+        .field("Flags", or_none(flags.join("\n")), false);
+
+    if region.parent.is_none() {
+        let subregions = db.get_subregions(&region.name).await?;
+        let tree = match subregions.is_empty() {
+            true => "*none*".to_owned(),
+            false => std::iter::once(format!("`{}`", region.label()))
+                .chain(subregions.iter().map(|s| format!("└ {s}")))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        };
+        embed.field("Subregions", tree, false);
     }
-    Ok(msg)
+
+    command.followup_embed(&ctx.http, embed).await?;
+
+    Ok(())
 }
 
-async fn find_collisions(
+async fn flag(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    subcmd: &CommandDataOption,
+    cfg: &conf::Config,
     db: &Database,
-    user_id: u64,
-    perimeter: &Perimeter,
-) -> Result<Vec<Region>> {
-    let plots = db.get_plots().await?;
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let subcmd = subcmd
+        .options
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Response does not contain any sub command option."))?;
 
-    let res = plots
-        .iter()
-        .filter(|p| p.owner != user_id && p.perimeter.intersects(perimeter))
-        .cloned()
-        .collect();
+    let plot_name = subcmd
+        .get_required_option_by_name("plotname")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Plot name value is not a string"))?
+        .to_lowercase();
 
-    Ok(res)
+    let Some(region) = plots::find_owned(db, guild, command.user.id.into(), &plot_name).await?
+    else {
+        command
+            .followup_err(&ctx.http, "You can not change the flags of this plot.")
+            .await?;
+        return Ok(());
+    };
+
+    if subcmd.name == "list" {
+        let flags = db
+            .get_plot_flags(&region.name)
+            .await?
+            .iter()
+            .map(|(flag, value)| format!("  ▫️ `{flag}`: {value}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        command
+            .followup_embed(
+                &ctx.http,
+                CreateEmbed::default()
+                    .color(Color::BLURPLE)
+                    .description(format!(
+                        "These are the flags of your plot `{plot_name}`:\n\n{}",
+                        or_none(flags)
+                    ))
+                    .to_owned(),
+            )
+            .await?;
+        return Ok(());
+    }
+
+    let flag = subcmd
+        .get_required_option_by_name("flag")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("Flag value is not a string"))?;
+
+    match subcmd.name.as_str() {
+        "set" => {
+            let value = subcmd
+                .get_required_option_by_name("value")?
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("Value is not a string"))?;
+
+            plots::set_flag(db, rc, &cfg.flags, &region, flag, Some(value)).await?;
+
+            command
+                .followup(
+                    &ctx.http,
+                    format!("The flag `{flag}` of your plot `{plot_name}` has been set! 🎉"),
+                )
+                .await?;
+        }
+        "clear" => {
+            plots::set_flag(db, rc, &cfg.flags, &region, flag, None).await?;
+
+            command
+                .followup(
+                    &ctx.http,
+                    format!("The flag `{flag}` of your plot `{plot_name}` has been removed."),
+                )
+                .await?;
+        }
+        _ => anyhow::bail!("Unregistered sub-sub command"),
+    }
+
+    Ok(())
+}
+
+// ---- HELPERS ----
+
+fn or_none(s: String) -> String {
+    if s.is_empty() {
+        "*none*".into()
+    } else {
+        s
+    }
+}
+
+fn get_pos_option(subcmd: &CommandDataOption, name: &str) -> Result<i64> {
+    let i = subcmd
+        .get_required_option_by_name(name)?
+        .as_i64()
+        .ok_or_else(|| anyhow::anyhow!("Value is not of type i64"))?;
+    Ok(i)
 }
@@ -0,0 +1,296 @@
+use crate::conf;
+use crate::db::Database;
+use crate::helpers::FollowUpHelper;
+use crate::idcache::IdCache;
+use crate::mc::{check_err, Servers};
+use crate::plots;
+use crate::quota::{self, Usage};
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::prelude::command::CommandOptionType;
+use serenity::model::prelude::component::ButtonStyle;
+use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::interaction::InteractionResponseType;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use std::time::Duration;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("unbind")
+        .description("Unbind your Minecraft account and remove it from the whitelist.")
+        .create_option(|o| {
+            o.name("transfer-to")
+                .description("A user to transfer your plots to.")
+                .kind(CommandOptionType::User)
+                .required(false)
+        })
+}
+
+/// What happens with the plots of the unbound user.
+enum PlotAction {
+    Delete,
+    Transfer { user_id: u64, username: String },
+    Orphan,
+}
+
+pub async fn run(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+    shutdown: &Shutdown,
+) -> Result<()> {
+    let Some(uuid) = db.get_user_by_id(guild.id, command.user.id).await? else {
+        command
+            .followup_err(&ctx.http, "You have not bound a Minecraft account.")
+            .await?;
+        return Ok(());
+    };
+
+    let username = idcache.get_username_by_uuid(&uuid).await?;
+    let plots = db.get_user_plots(guild.id, command.user.id).await?;
+    let servers = db.get_user_servers(guild.id, command.user.id).await?;
+
+    let transfer_to = command
+        .data
+        .options
+        .iter()
+        .find(|o| o.name == "transfer-to")
+        .and_then(|o| o.value.as_ref())
+        .and_then(|v| v.as_str())
+        .map(|v| v.parse::<u64>())
+        .transpose()?;
+
+    let transfer_to = match transfer_to {
+        Some(user_id) if user_id == u64::from(command.user.id) => {
+            command
+                .followup_err(&ctx.http, "You can not transfer your plots to yourself.")
+                .await?;
+            return Ok(());
+        }
+        Some(user_id) => match db.get_user_by_id(guild.id, user_id).await? {
+            Some(uuid) => {
+                let name = idcache.get_username_by_uuid(&uuid).await?;
+
+                // Pending plots are withdrawn instead of transferred.
+                let transferred = Usage::of(plots.iter().filter(|p| !p.pending));
+                let usage = Usage::of(&db.get_user_plots(guild.id, user_id).await?);
+                if quota::check(&guild.quota, usage, transferred.plots, transferred.area).is_err() {
+                    command
+                        .followup_err(
+                            &ctx.http,
+                            format!(
+                                "Your plots can not be transferred to {name}, as {name} \
+                                 would exceed the quota."
+                            ),
+                        )
+                        .await?;
+                    return Ok(());
+                }
+
+                Some((user_id, name))
+            }
+            None => {
+                command
+                    .followup_err(
+                        &ctx.http,
+                        "The user you want to transfer your plots to has not bound a Minecraft account.",
+                    )
+                    .await?;
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    let mut description = format!(
+        "Do you really want to unbind your Minecraft account {username}? \
+         It will also be removed from the whitelist."
+    );
+    if !plots.is_empty() {
+        let list = plots
+            .iter()
+            .map(|p| format!("  ▫️ {p}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        description.push_str(&format!(
+            "\n\nWhat should happen with your plots?\n\n{list}\n\n\
+             Kept plots remain on the server without an owner."
+        ));
+    }
+
+    let delete_id = xid::new().to_string();
+    let transfer_id = xid::new().to_string();
+    let orphan_id = xid::new().to_string();
+    let cancel_id = xid::new().to_string();
+    let m = command
+        .create_followup_message(&ctx.http, |msg| {
+            msg.add_embed(
+                CreateEmbed::default()
+                    .description(&description)
+                    .color(Color::ORANGE)
+                    .to_owned(),
+            )
+            .components(|c| {
+                c.create_action_row(|row| {
+                    row.create_button(|btn| {
+                        btn.custom_id(&delete_id)
+                            .style(ButtonStyle::Danger)
+                            .label(if plots.is_empty() { "Unbind" } else { "Delete Plots" })
+                    });
+                    if let (false, Some((_, name))) = (plots.is_empty(), &transfer_to) {
+                        row.create_button(|btn| {
+                            btn.custom_id(&transfer_id)
+                                .style(ButtonStyle::Primary)
+                                .label(format!("Transfer Plots to {name}"))
+                        });
+                    }
+                    if !plots.is_empty() {
+                        row.create_button(|btn| {
+                            btn.custom_id(&orphan_id)
+                                .style(ButtonStyle::Secondary)
+                                .label("Keep Plots")
+                        });
+                    }
+                    row.create_button(|btn| {
+                        btn.custom_id(&cancel_id)
+                            .style(ButtonStyle::Secondary)
+                            .label("Cancel")
+                    })
+                })
+            })
+        })
+        .await?;
+
+    let interaction = shutdown
+        .interruptible(
+            m.await_component_interaction(ctx)
+                .timeout(Duration::from_secs(60)),
+        )
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Timed out."))?;
+
+    let action = match interaction.data.custom_id.as_str() {
+        id if id == delete_id => PlotAction::Delete,
+        id if id == orphan_id => PlotAction::Orphan,
+        id if id == transfer_id => {
+            let (user_id, username) = transfer_to
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("No user to transfer the plots to."))?;
+            PlotAction::Transfer { user_id, username }
+        }
+        _ => {
+            interaction
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|d| {
+                            d.add_embed(
+                                CreateEmbed::default()
+                                    .description("Action canceled.")
+                                    .to_owned(),
+                            )
+                            .components(|c| c)
+                        })
+                })
+                .await?;
+            return Ok(());
+        }
+    };
+
+    // Pending plots do not exist in WorldGuard yet, and deleted
+    // plots are removed from it below.
+    let kept = plots
+        .iter()
+        .filter(|p| !p.pending && !matches!(action, PlotAction::Delete));
+    for plot in kept {
+        let mut conn = rc
+            .get(&plot.server)?
+            .get_conn()
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+        match &action {
+            PlotAction::Delete => {}
+            PlotAction::Transfer { username, .. } => {
+                check_err(conn.cmd(&format!(
+                    "rg removeowner -w {} -a {}",
+                    plot.world, plot.name
+                )))?;
+                check_err(conn.cmd(&format!(
+                    "rg addowner -w {} {} {username}",
+                    plot.world, plot.name
+                )))?;
+            }
+            PlotAction::Orphan => {
+                check_err(conn.cmd(&format!(
+                    "rg removeowner -w {} -a {}",
+                    plot.world, plot.name
+                )))?;
+            }
+        }
+    }
+
+    for server in servers {
+        let mut conn = rc
+            .get(&server)?
+            .get_conn()
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+        conn.cmd(&idcache.provider().whitelist_command("remove", &username))
+            .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+
+    for plot in &plots {
+        // Requests which have not been approved yet are withdrawn.
+        if plot.pending {
+            db.delete_plot(&plot.name).await?;
+            continue;
+        }
+
+        match &action {
+            // Subregions are deleted along with their parent. Deleted
+            // plots are kept in the trash, like with `/region delete`.
+            PlotAction::Delete if plot.parent.is_some() => {}
+            PlotAction::Delete => plots::delete(db, rc, plot).await?,
+            PlotAction::Transfer { user_id, .. } => {
+                db.set_plot_owner(&plot.name, Some(*user_id)).await?
+            }
+            PlotAction::Orphan => db.set_plot_owner(&plot.name, None).await?,
+        }
+    }
+
+    // Removed plots would otherwise be deleted along with the user,
+    // so they follow the active ones or lose their owner.
+    for plot in db.get_removed_user_plots(guild.id, command.user.id).await? {
+        match &action {
+            PlotAction::Transfer { user_id, .. } => {
+                db.set_plot_owner(&plot.name, Some(*user_id)).await?
+            }
+            PlotAction::Delete | PlotAction::Orphan => db.set_plot_owner(&plot.name, None).await?,
+        }
+    }
+
+    db.delete_user(guild.id, command.user.id).await?;
+
+    interaction
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| {
+                    d.add_embed(
+                        CreateEmbed::default()
+                            .color(Color::FOOYOO)
+                            .description(format!(
+                                "Your Minecraft account {username} has been unbound. 👋"
+                            ))
+                            .to_owned(),
+                    )
+                    .components(|c| c)
+                })
+        })
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,60 @@
+use crate::conf;
+use crate::db::Database;
+use crate::helpers::FollowUpHelper;
+use crate::idcache::IdCache;
+use crate::quota::{Limit, Usage};
+use anyhow::Result;
+use serenity::builder::{CreateApplicationCommand, CreateEmbed};
+use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+
+pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
+    command
+        .name("whoami")
+        .description("Show the Minecraft account bound to your Discord account.")
+}
+
+pub async fn run(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    db: &Database,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let Some(uuid) = db.get_user_by_id(guild.id, command.user.id).await? else {
+        command
+            .followup_err(
+                &ctx.http,
+                "You have not bound a Minecraft account. Use the `/bind` command to do so.",
+            )
+            .await?;
+        return Ok(());
+    };
+
+    let username = idcache
+        .get_username_by_uuid(&uuid)
+        .await
+        .unwrap_or_else(|_| "*unknown*".into());
+
+    let usage = Usage::of(&db.get_user_plots(guild.id, command.user.id).await?);
+
+    command
+        .followup_embed(
+            &ctx.http,
+            CreateEmbed::default()
+                .color(Color::BLURPLE)
+                .field("Minecraft Name", username, true)
+                .field("UUID", format!("`{uuid:#}`"), true)
+                .field("Plots", Limit(usage.plots, guild.quota.max_plots), false)
+                .field(
+                    "Area",
+                    format!("{}m²", Limit(usage.area, guild.quota.max_area)),
+                    false,
+                )
+                .to_owned(),
+        )
+        .await?;
+
+    Ok(())
+}
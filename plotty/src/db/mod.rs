@@ -1,6 +1,9 @@
-use crate::models::{Perimeter, Point, Region, User};
+use crate::metrics;
+use crate::models::{Perimeter, Point, Profile, Region, Revision, User, Zone};
 use anyhow::Result;
+use minecraft_uuid::MinecraftUuid;
 use serenity::futures::TryStreamExt;
+use sqlx::mysql::MySqlRow;
 use sqlx::{MySqlPool, Row};
 
 pub struct Database {
@@ -20,22 +23,65 @@ impl Database {
         Ok(())
     }
 
-    pub async fn get_user_by_id<I: Into<u64> + Copy>(&self, id: I) -> Result<Option<String>> {
-        let mut rows = sqlx::query("SELECT mc_name FROM users WHERE user_id = ?")
+    /// Waits for running queries and closes all connections.
+    pub async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    pub async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Assigns all rows created before multiple guilds were
+    /// supported to the given guild.
+    pub async fn adopt_unscoped(&self, guild_id: u64) -> Result<()> {
+        // Updating users cascades to all rows referencing them.
+        sqlx::query("UPDATE users SET guild_id = ? WHERE guild_id = 0")
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+
+        for table in ["plots", "plot_ids", "whitelists"] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET guild_id = ? WHERE guild_id = 0"
+            ))
+            .bind(guild_id)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn get_user_by_id<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        id: I,
+    ) -> Result<Option<MinecraftUuid>> {
+        let _timer = metrics::db_timer("get_user_by_id");
+        let mut rows = sqlx::query("SELECT mc_uuid FROM users WHERE guild_id = ? AND user_id = ?")
+            .bind(guild_id)
             .bind(id.into())
             .fetch(&self.pool);
 
         if let Some(row) = rows.try_next().await? {
-            let name: String = row.try_get("mc_name")?;
-            Ok(Some(name))
+            let uuid = row.try_get("mc_uuid")?;
+            Ok(Some(uuid))
         } else {
             Ok(None)
         }
     }
 
-    pub async fn get_user_by_mcname(&self, mcname: &str) -> Result<Option<u64>> {
-        let mut rows = sqlx::query("SELECT user_id FROM users WHERE mc_name = ?")
-            .bind(mcname)
+    pub async fn get_user_by_uuid(
+        &self,
+        guild_id: u64,
+        uuid: &MinecraftUuid,
+    ) -> Result<Option<u64>> {
+        let _timer = metrics::db_timer("get_user_by_uuid");
+        let mut rows = sqlx::query("SELECT user_id FROM users WHERE guild_id = ? AND mc_uuid = ?")
+            .bind(guild_id)
+            .bind(uuid)
             .fetch(&self.pool);
 
         if let Some(row) = rows.try_next().await? {
@@ -46,32 +92,121 @@ impl Database {
         }
     }
 
-    pub async fn set_user<I: Into<u64> + Copy>(&self, id: I, mcname: &str) -> Result<()> {
-        let res = sqlx::query("UPDATE users SET mc_name = ? WHERE user_id = ?")
-            .bind(mcname)
+    pub async fn set_user<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        id: I,
+        uuid: &MinecraftUuid,
+        name: &str,
+    ) -> Result<()> {
+        let _timer = metrics::db_timer("set_user");
+        let res = sqlx::query(
+            "UPDATE users SET mc_uuid = ?, mc_name = ? WHERE guild_id = ? AND user_id = ?",
+        )
+        .bind(uuid)
+        .bind(name)
+        .bind(guild_id)
+        .bind(id.into())
+        .execute(&self.pool)
+        .await?;
+
+        if res.rows_affected() == 0 {
+            sqlx::query(
+                "INSERT INTO users (guild_id, user_id, mc_uuid, mc_name) VALUES (?, ?, ?, ?)",
+            )
+            .bind(guild_id)
             .bind(id.into())
+            .bind(uuid)
+            .bind(name)
             .execute(&self.pool)
             .await?;
+        }
 
-        if res.rows_affected() == 0 {
-            sqlx::query("INSERT INTO users (user_id, mc_name) VALUES (?, ?)")
+        Ok(())
+    }
+
+    /// Returns the name the account has been bound with in any guild.
+    pub async fn get_bound_name(&self, uuid: &MinecraftUuid) -> Result<Option<String>> {
+        let _timer = metrics::db_timer("get_bound_name");
+        let row = sqlx::query(
+            "SELECT mc_name FROM users WHERE mc_uuid = ? AND mc_name IS NOT NULL LIMIT 1",
+        )
+        .bind(uuid)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| r.try_get("mc_name")).transpose()?)
+    }
+
+    /// Updates the bound name of the account after a name change.
+    pub async fn set_bound_name(&self, uuid: &MinecraftUuid, name: &str) -> Result<()> {
+        let _timer = metrics::db_timer("set_bound_name");
+        sqlx::query("UPDATE users SET mc_name = ? WHERE mc_uuid = ?")
+            .bind(name)
+            .bind(uuid)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_user<I: Into<u64> + Copy>(&self, guild_id: u64, id: I) -> Result<()> {
+        let _timer = metrics::db_timer("delete_user");
+        sqlx::query("DELETE FROM users WHERE guild_id = ? AND user_id = ?")
+            .bind(guild_id)
+            .bind(id.into())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_user_servers<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        id: I,
+    ) -> Result<Vec<String>> {
+        let _timer = metrics::db_timer("get_user_servers");
+        let mut rows =
+            sqlx::query("SELECT server FROM whitelists WHERE guild_id = ? AND user_id = ?")
+                .bind(guild_id)
                 .bind(id.into())
-                .bind(mcname)
-                .execute(&self.pool)
-                .await?;
+                .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(row.try_get("server")?);
         }
 
+        Ok(res)
+    }
+
+    pub async fn add_user_server<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        id: I,
+        server: &str,
+    ) -> Result<()> {
+        let _timer = metrics::db_timer("add_user_server");
+        sqlx::query("INSERT IGNORE INTO whitelists (guild_id, user_id, server) VALUES (?, ?, ?)")
+            .bind(guild_id)
+            .bind(id.into())
+            .bind(server)
+            .execute(&self.pool)
+            .await?;
         Ok(())
     }
 
+    /// Lists the users of all guilds.
     pub async fn list_users(&self) -> Result<Vec<User>> {
-        let mut rows = sqlx::query("SELECT user_id, mc_name FROM users").fetch(&self.pool);
+        let _timer = metrics::db_timer("list_users");
+        let mut rows =
+            sqlx::query("SELECT guild_id, user_id, mc_uuid FROM users").fetch(&self.pool);
 
         let mut res = Vec::new();
         while let Some(row) = rows.try_next().await? {
             let user = User {
+                guild_id: row.try_get("guild_id")?,
                 discord_id: row.try_get("user_id")?,
-                minecraft_uid: row.try_get("mc_name")?,
+                minecraft_uid: row.try_get("mc_uuid")?,
             };
             res.push(user);
         }
@@ -79,84 +214,224 @@ impl Database {
         Ok(res)
     }
 
+    /// Lists the plots of all guilds.
     pub async fn get_plots(&self) -> Result<Vec<Region>> {
-        let mut rows =
-            sqlx::query("SELECT plot_id, user_id, ax, az, bx, bz FROM plots").fetch(&self.pool);
+        let _timer = metrics::db_timer("get_plots");
+        let mut rows = sqlx::query(
+            "SELECT plot_id, guild_id, user_id, server, world, ax, az, bx, bz, pending, display_name, parent_id \
+             FROM plots \
+             WHERE expired_at IS NULL AND deleted_at IS NULL",
+        )
+        .fetch(&self.pool);
 
         let mut res = Vec::new();
         while let Some(row) = rows.try_next().await? {
-            let region = Region {
-                owner: row.try_get("user_id")?,
-                name: row.try_get("plot_id")?,
-                perimeter: Perimeter(
-                    Point(row.try_get("ax")?, row.try_get("az")?),
-                    Point(row.try_get("bx")?, row.try_get("bz")?),
-                ),
-            };
-            res.push(region);
+            res.push(region_from_row(&row)?);
         }
 
         Ok(res)
     }
 
-    pub async fn get_user_plots<I: Into<u64> + Copy>(&self, user_id: I) -> Result<Vec<Region>> {
-        let mut rows = sqlx::query("SELECT plot_id, ax, az, bx, bz FROM plots WHERE user_id = ?")
-            .bind(user_id.into())
-            .fetch(&self.pool);
+    pub async fn get_user_plots<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        user_id: I,
+    ) -> Result<Vec<Region>> {
+        let _timer = metrics::db_timer("get_user_plots");
+        let mut rows = sqlx::query(
+            "SELECT plot_id, guild_id, user_id, server, world, ax, az, bx, bz, pending, display_name, parent_id \
+             FROM plots \
+             WHERE guild_id = ? AND user_id = ? AND expired_at IS NULL AND deleted_at IS NULL",
+        )
+        .bind(guild_id)
+        .bind(user_id.into())
+        .fetch(&self.pool);
 
         let mut res = Vec::new();
         while let Some(row) = rows.try_next().await? {
-            let region = Region {
-                owner: user_id.into(),
-                name: row.try_get("plot_id")?,
-                perimeter: Perimeter(
-                    Point(row.try_get("ax")?, row.try_get("az")?),
-                    Point(row.try_get("bx")?, row.try_get("bz")?),
-                ),
-            };
-            res.push(region);
+            res.push(region_from_row(&row)?);
+        }
+
+        Ok(res)
+    }
+
+    /// Lists the expired and deleted plots of the user which can
+    /// still be restored.
+    pub async fn get_removed_user_plots<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        user_id: I,
+    ) -> Result<Vec<Region>> {
+        let _timer = metrics::db_timer("get_removed_user_plots");
+        let mut rows = sqlx::query(
+            "SELECT plot_id, guild_id, user_id, server, world, ax, az, bx, bz, pending, display_name, parent_id \
+             FROM plots \
+             WHERE guild_id = ? AND user_id = ? \
+             AND (expired_at IS NOT NULL OR deleted_at IS NOT NULL)",
+        )
+        .bind(guild_id)
+        .bind(user_id.into())
+        .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(region_from_row(&row)?);
         }
 
         Ok(res)
     }
 
     pub async fn get_plot_by_name(&self, name: &str) -> Result<Option<Region>> {
-        let mut rows = sqlx::query("SELECT user_id, ax, az, bx, bz FROM plots WHERE plot_id = ?")
-            .bind(name)
-            .fetch(&self.pool);
+        let _timer = metrics::db_timer("get_plot_by_name");
+        let mut rows = sqlx::query(
+            "SELECT plot_id, guild_id, user_id, server, world, ax, az, bx, bz, pending, display_name, parent_id \
+             FROM plots \
+             WHERE plot_id = ? AND expired_at IS NULL AND deleted_at IS NULL",
+        )
+        .bind(name)
+        .fetch(&self.pool);
 
         if let Some(row) = rows.try_next().await? {
-            let region = Region {
-                name: name.to_owned(),
-                owner: row.try_get("user_id")?,
-                perimeter: Perimeter(
-                    Point(row.try_get("ax")?, row.try_get("az")?),
-                    Point(row.try_get("bx")?, row.try_get("bz")?),
-                ),
-            };
-            Ok(Some(region))
+            Ok(Some(region_from_row(&row)?))
         } else {
             Ok(None)
         }
     }
 
+    /// Returns the active plot of the guild with the given ID or
+    /// display name.
+    pub async fn find_plot(&self, guild_id: u64, name: &str) -> Result<Option<Region>> {
+        let _timer = metrics::db_timer("find_plot");
+        let mut rows = sqlx::query(
+            "SELECT plot_id, guild_id, user_id, server, world, ax, az, bx, bz, pending, display_name, parent_id \
+             FROM plots WHERE guild_id = ? AND (plot_id = ? OR display_name = ?) \
+             AND expired_at IS NULL AND deleted_at IS NULL \
+             ORDER BY plot_id = ? DESC",
+        )
+        .bind(guild_id)
+        .bind(name)
+        .bind(name)
+        .bind(name)
+        .fetch(&self.pool);
+
+        if let Some(row) = rows.try_next().await? {
+            Ok(Some(region_from_row(&row)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Whether the name is used as ID or display name by any plot of
+    /// the guild, including deleted and expired ones.
+    pub async fn plot_name_taken(&self, guild_id: u64, name: &str) -> Result<bool> {
+        let _timer = metrics::db_timer("plot_name_taken");
+        let row = sqlx::query(
+            "SELECT 1 FROM plots WHERE guild_id = ? AND (plot_id = ? OR display_name = ?)",
+        )
+        .bind(guild_id)
+        .bind(name)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.is_some())
+    }
+
+    /// Whether the region ID is used by any plot, including deleted
+    /// and expired ones. Unlike display names, region IDs are unique
+    /// across guilds, as guilds may share servers.
+    pub async fn plot_id_taken(&self, name: &str) -> Result<bool> {
+        let _timer = metrics::db_timer("plot_id_taken");
+        let row = sqlx::query("SELECT 1 FROM plots WHERE plot_id = ?")
+            .bind(name)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    pub async fn set_plot_display_name(&self, plot_name: &str, name: Option<&str>) -> Result<()> {
+        let _timer = metrics::db_timer("set_plot_display_name");
+        sqlx::query("UPDATE plots SET display_name = ? WHERE plot_id = ?")
+            .bind(name)
+            .bind(plot_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Changes the region ID of the plot and clears its display name.
+    /// Members, flags, revisions and subregions follow the plot.
+    pub async fn rename_plot(&self, plot_name: &str, new_name: &str) -> Result<()> {
+        let _timer = metrics::db_timer("rename_plot");
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("UPDATE plots SET plot_id = ?, display_name = NULL WHERE plot_id = ?")
+            .bind(new_name)
+            .bind(plot_name)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("UPDATE plots SET parent_id = ? WHERE parent_id = ?")
+            .bind(new_name)
+            .bind(plot_name)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Lists the active subregions of the plot.
+    pub async fn get_subregions(&self, plot_name: &str) -> Result<Vec<Region>> {
+        let _timer = metrics::db_timer("get_subregions");
+        let mut rows = sqlx::query(
+            "SELECT plot_id, guild_id, user_id, server, world, ax, az, bx, bz, pending, display_name, parent_id \
+             FROM plots \
+             WHERE parent_id = ? AND expired_at IS NULL AND deleted_at IS NULL",
+        )
+        .bind(plot_name)
+        .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(region_from_row(&row)?);
+        }
+
+        Ok(res)
+    }
+
     pub async fn add_plot(&self, region: &Region) -> Result<()> {
+        let _timer = metrics::db_timer("add_plot");
         sqlx::query(
-            "INSERT INTO plots (user_id, plot_id, ax, az, bx, bz) VALUES (?, ?, ?, ?, ?, ?)",
+            "INSERT INTO plots (guild_id, user_id, plot_id, server, world, ax, az, bx, bz, pending, parent_id) \
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
         )
+        .bind(region.guild_id)
         .bind(region.owner)
         .bind(&region.name)
+        .bind(&region.server)
+        .bind(&region.world)
         .bind(region.perimeter.0 .0)
         .bind(region.perimeter.0 .1)
         .bind(region.perimeter.1 .0)
         .bind(region.perimeter.1 .1)
+        .bind(region.pending)
+        .bind(&region.parent)
         .execute(&self.pool)
         .await?;
         Ok(())
     }
 
+    /// Marks a pending plot as approved.
+    pub async fn approve_plot(&self, plot_name: &str) -> Result<()> {
+        let _timer = metrics::db_timer("approve_plot");
+        sqlx::query("UPDATE plots SET pending = FALSE WHERE plot_id = ?")
+            .bind(plot_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
     pub async fn update_plot(&self, region: &Region) -> Result<()> {
-        sqlx::query("UPDATE plots SET ax = ?, az = ?, bx = ?, bz = ? WHERE plot_id = ?")
+        let _timer = metrics::db_timer("update_plot");
+        sqlx::query("UPDATE plots SET world = ?, ax = ?, az = ?, bx = ?, bz = ? WHERE plot_id = ?")
+            .bind(&region.world)
             .bind(region.perimeter.0 .0)
             .bind(region.perimeter.0 .1)
             .bind(region.perimeter.1 .0)
@@ -167,19 +442,389 @@ impl Database {
         Ok(())
     }
 
+    /// Lists the previous perimeters of the plot, latest first.
+    pub async fn get_plot_revisions(&self, plot_name: &str) -> Result<Vec<Revision>> {
+        let _timer = metrics::db_timer("get_plot_revisions");
+        let mut rows = sqlx::query(
+            "SELECT revision, world, ax, az, bx, bz, UNIX_TIMESTAMP(created_at) AS created_at \
+             FROM plot_revisions WHERE plot_id = ? ORDER BY revision DESC",
+        )
+        .bind(plot_name)
+        .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(Revision {
+                number: row.try_get("revision")?,
+                world: row.try_get("world")?,
+                perimeter: Perimeter(
+                    Point(row.try_get("ax")?, row.try_get("az")?),
+                    Point(row.try_get("bx")?, row.try_get("bz")?),
+                ),
+                created_at: row.try_get("created_at")?,
+            });
+        }
+
+        Ok(res)
+    }
+
+    /// Stores the current perimeter of the plot as its next revision.
+    pub async fn add_plot_revision(&self, region: &Region) -> Result<()> {
+        let _timer = metrics::db_timer("add_plot_revision");
+        sqlx::query(
+            "INSERT INTO plot_revisions (plot_id, revision, world, ax, az, bx, bz, created_at) \
+             SELECT ?, COALESCE(MAX(revision), 0) + 1, ?, ?, ?, ?, ?, NOW() \
+             FROM plot_revisions WHERE plot_id = ?",
+        )
+        .bind(&region.name)
+        .bind(&region.world)
+        .bind(region.perimeter.0 .0)
+        .bind(region.perimeter.0 .1)
+        .bind(region.perimeter.1 .0)
+        .bind(region.perimeter.1 .1)
+        .bind(&region.name)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Marks the plot and its subregions as deleted, keeping them
+    /// until they are purged.
+    pub async fn trash_plot(&self, plot_name: &str) -> Result<()> {
+        let _timer = metrics::db_timer("trash_plot");
+        sqlx::query("UPDATE plots SET deleted_at = NOW() WHERE plot_id = ? OR parent_id = ?")
+            .bind(plot_name)
+            .bind(plot_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes the plot along with its members, flags and subregions
+    /// for good.
     pub async fn delete_plot(&self, plot_name: &str) -> Result<()> {
-        sqlx::query("DELETE FROM plots WHERE plot_id = ?")
+        let _timer = metrics::db_timer("delete_plot");
+        sqlx::query("DELETE FROM plots WHERE plot_id = ? OR parent_id = ?")
+            .bind(plot_name)
+            .bind(plot_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_plot_owner(&self, plot_name: &str, owner: Option<u64>) -> Result<()> {
+        let _timer = metrics::db_timer("set_plot_owner");
+        sqlx::query("UPDATE plots SET user_id = ? WHERE plot_id = ?")
+            .bind(owner)
             .bind(plot_name)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
-    pub async fn get_plot_user_id<I: Into<u64> + Copy>(&self, user_id: I) -> Result<Option<i64>> {
-        let mut rows = sqlx::query("SELECT plot_inc FROM plot_ids WHERE user_id = ?")
-            .bind(user_id.into())
+    pub async fn get_plot_members(&self, plot_name: &str) -> Result<Vec<MinecraftUuid>> {
+        let _timer = metrics::db_timer("get_plot_members");
+        let mut rows = sqlx::query("SELECT mc_uuid FROM plot_members WHERE plot_id = ?")
+            .bind(plot_name)
             .fetch(&self.pool);
 
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(row.try_get("mc_uuid")?);
+        }
+
+        Ok(res)
+    }
+
+    pub async fn add_plot_member(&self, plot_name: &str, uuid: &MinecraftUuid) -> Result<()> {
+        let _timer = metrics::db_timer("add_plot_member");
+        sqlx::query("INSERT IGNORE INTO plot_members (plot_id, mc_uuid) VALUES (?, ?)")
+            .bind(plot_name)
+            .bind(uuid)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn remove_plot_member(&self, plot_name: &str, uuid: &MinecraftUuid) -> Result<()> {
+        let _timer = metrics::db_timer("remove_plot_member");
+        sqlx::query("DELETE FROM plot_members WHERE plot_id = ? AND mc_uuid = ?")
+            .bind(plot_name)
+            .bind(uuid)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Returns the flags set on the plot as name and value,
+    /// ordered by name.
+    pub async fn get_plot_flags(&self, plot_name: &str) -> Result<Vec<(String, String)>> {
+        let _timer = metrics::db_timer("get_plot_flags");
+        let mut rows =
+            sqlx::query("SELECT flag, value FROM plot_flags WHERE plot_id = ? ORDER BY flag")
+                .bind(plot_name)
+                .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push((row.try_get("flag")?, row.try_get("value")?));
+        }
+
+        Ok(res)
+    }
+
+    pub async fn set_plot_flag(&self, plot_name: &str, flag: &str, value: &str) -> Result<()> {
+        let _timer = metrics::db_timer("set_plot_flag");
+        sqlx::query(
+            "INSERT INTO plot_flags (plot_id, flag, value) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE value = VALUES(value)",
+        )
+        .bind(plot_name)
+        .bind(flag)
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn clear_plot_flag(&self, plot_name: &str, flag: &str) -> Result<()> {
+        let _timer = metrics::db_timer("clear_plot_flag");
+        sqlx::query("DELETE FROM plot_flags WHERE plot_id = ? AND flag = ?")
+            .bind(plot_name)
+            .bind(flag)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Lists the zones of all servers, ordered by name.
+    pub async fn get_zones(&self) -> Result<Vec<Zone>> {
+        let _timer = metrics::db_timer("get_zones");
+        let mut rows =
+            sqlx::query("SELECT server, name, world, points, approval FROM zones ORDER BY name")
+                .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            let points: Vec<(i64, i64)> = serde_json::from_str(row.try_get("points")?)?;
+            res.push(Zone {
+                server: row.try_get("server")?,
+                name: row.try_get("name")?,
+                world: row.try_get("world")?,
+                points: points.into_iter().map(|(x, z)| Point(x, z)).collect(),
+                approval: row.try_get("approval")?,
+            });
+        }
+
+        Ok(res)
+    }
+
+    /// Adds the zone or replaces the zone with the same name
+    /// on the same server.
+    pub async fn set_zone(&self, zone: &Zone) -> Result<()> {
+        let _timer = metrics::db_timer("set_zone");
+        let points: Vec<_> = zone.points.iter().map(|p| (p.0, p.1)).collect();
+        sqlx::query(
+            "INSERT INTO zones (server, name, world, points, approval) VALUES (?, ?, ?, ?, ?) \
+             ON DUPLICATE KEY UPDATE world = VALUES(world), points = VALUES(points), \
+             approval = VALUES(approval)",
+        )
+        .bind(&zone.server)
+        .bind(&zone.name)
+        .bind(&zone.world)
+        .bind(serde_json::to_string(&points)?)
+        .bind(zone.approval)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Returns whether the zone existed.
+    pub async fn delete_zone(&self, server: &str, name: &str) -> Result<bool> {
+        let _timer = metrics::db_timer("delete_zone");
+        let res = sqlx::query("DELETE FROM zones WHERE server = ? AND name = ?")
+            .bind(server)
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected() > 0)
+    }
+
+    /// Marks the plot and its subregions as expired.
+    pub async fn expire_plot(&self, plot_name: &str) -> Result<()> {
+        let _timer = metrics::db_timer("expire_plot");
+        sqlx::query("UPDATE plots SET expired_at = NOW() WHERE plot_id = ? OR parent_id = ?")
+            .bind(plot_name)
+            .bind(plot_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Restores the plot along with its subregions.
+    pub async fn restore_plot(&self, plot_name: &str) -> Result<()> {
+        let _timer = metrics::db_timer("restore_plot");
+        sqlx::query("UPDATE plots SET expired_at = NULL, deleted_at = NULL WHERE plot_id = ? OR parent_id = ?")
+            .bind(plot_name)
+            .bind(plot_name)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Deletes plots which have been expired for more than the
+    /// given number of days and returns how many.
+    pub async fn purge_expired_plots(&self, days: u64) -> Result<u64> {
+        let _timer = metrics::db_timer("purge_expired_plots");
+        let res = sqlx::query("DELETE FROM plots WHERE expired_at < NOW() - INTERVAL ? DAY")
+            .bind(days)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Deletes plots which have been deleted more than the given
+    /// number of days ago and returns how many.
+    pub async fn purge_deleted_plots(&self, days: u64) -> Result<u64> {
+        let _timer = metrics::db_timer("purge_deleted_plots");
+        let res = sqlx::query("DELETE FROM plots WHERE deleted_at < NOW() - INTERVAL ? DAY")
+            .bind(days)
+            .execute(&self.pool)
+            .await?;
+        Ok(res.rows_affected())
+    }
+
+    /// Records that the player has been seen online just now.
+    pub async fn touch_user(&self, uuid: &MinecraftUuid) -> Result<()> {
+        let _timer = metrics::db_timer("touch_user");
+        sqlx::query(
+            "INSERT INTO user_activity (mc_uuid, seen_at) VALUES (?, NOW()) \
+             ON DUPLICATE KEY UPDATE seen_at = NOW(), warned_at = NULL",
+        )
+        .bind(uuid)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Starts tracking the activity of users bound since the last
+    /// call, counting them as seen just now.
+    pub async fn track_new_users(&self) -> Result<()> {
+        let _timer = metrics::db_timer("track_new_users");
+        sqlx::query(
+            "INSERT IGNORE INTO user_activity (mc_uuid, seen_at) \
+             SELECT DISTINCT mc_uuid, NOW() FROM users WHERE mc_uuid IS NOT NULL",
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn set_user_warned(&self, uuid: &MinecraftUuid) -> Result<()> {
+        let _timer = metrics::db_timer("set_user_warned");
+        sqlx::query("UPDATE user_activity SET warned_at = NOW() WHERE mc_uuid = ?")
+            .bind(uuid)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Lists users with plots who have not been seen for the given
+    /// number of days and have not been warned yet, excluding
+    /// exempted users.
+    pub async fn get_users_to_warn(&self, days: u64) -> Result<Vec<User>> {
+        let _timer = metrics::db_timer("get_users_to_warn");
+        self.get_inactive_users(
+            "a.seen_at < NOW() - INTERVAL ? DAY AND a.warned_at IS NULL",
+            &[days],
+        )
+        .await
+    }
+
+    /// Lists users with plots who have not been seen for the given
+    /// number of days and have been warned at least `warned_days`
+    /// ago, excluding exempted users.
+    pub async fn get_users_to_expire(&self, days: u64, warned_days: u64) -> Result<Vec<User>> {
+        let _timer = metrics::db_timer("get_users_to_expire");
+        self.get_inactive_users(
+            "a.seen_at < NOW() - INTERVAL ? DAY \
+             AND (? = 0 OR a.warned_at < NOW() - INTERVAL ? DAY)",
+            &[days, warned_days, warned_days],
+        )
+        .await
+    }
+
+    async fn get_inactive_users(&self, condition: &str, args: &[u64]) -> Result<Vec<User>> {
+        let sql = format!(
+            "SELECT u.guild_id, u.user_id, u.mc_uuid FROM users u \
+             JOIN user_activity a ON a.mc_uuid = u.mc_uuid \
+             WHERE {condition} \
+             AND NOT EXISTS (SELECT 1 FROM expiry_exemptions e \
+                 WHERE e.guild_id = u.guild_id AND e.user_id = u.user_id) \
+             AND EXISTS (SELECT 1 FROM plots p \
+                 WHERE p.guild_id = u.guild_id AND p.user_id = u.user_id \
+                 AND p.expired_at IS NULL AND p.deleted_at IS NULL)"
+        );
+
+        let mut query = sqlx::query(&sql);
+        for arg in args {
+            query = query.bind(arg);
+        }
+        let mut rows = query.fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(User {
+                guild_id: row.try_get("guild_id")?,
+                discord_id: row.try_get("user_id")?,
+                minecraft_uid: row.try_get("mc_uuid")?,
+            });
+        }
+
+        Ok(res)
+    }
+
+    pub async fn get_expiry_exemptions(&self, guild_id: u64) -> Result<Vec<u64>> {
+        let _timer = metrics::db_timer("get_expiry_exemptions");
+        let mut rows = sqlx::query("SELECT user_id FROM expiry_exemptions WHERE guild_id = ?")
+            .bind(guild_id)
+            .fetch(&self.pool);
+
+        let mut res = Vec::new();
+        while let Some(row) = rows.try_next().await? {
+            res.push(row.try_get("user_id")?);
+        }
+
+        Ok(res)
+    }
+
+    /// The user must be bound in the guild.
+    pub async fn set_expiry_exempt(&self, guild_id: u64, user_id: u64, exempt: bool) -> Result<()> {
+        let _timer = metrics::db_timer("set_expiry_exempt");
+        let sql = match exempt {
+            true => "INSERT IGNORE INTO expiry_exemptions (guild_id, user_id) VALUES (?, ?)",
+            false => "DELETE FROM expiry_exemptions WHERE guild_id = ? AND user_id = ?",
+        };
+        sqlx::query(sql)
+            .bind(guild_id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_plot_user_id<I: Into<u64> + Copy>(
+        &self,
+        guild_id: u64,
+        user_id: I,
+    ) -> Result<Option<i64>> {
+        let _timer = metrics::db_timer("get_plot_user_id");
+        let mut rows =
+            sqlx::query("SELECT plot_inc FROM plot_ids WHERE guild_id = ? AND user_id = ?")
+                .bind(guild_id)
+                .bind(user_id.into())
+                .fetch(&self.pool);
+
         if let Some(row) = rows.try_next().await? {
             let inc = row.try_get("plot_inc")?;
             Ok(Some(inc))
@@ -188,14 +833,19 @@ impl Database {
         }
     }
 
-    pub async fn inc_plot_user_id<I: Into<u64> + Copy>(&self, id: I) -> Result<()> {
-        let res = sqlx::query("UPDATE plot_ids SET plot_inc = plot_inc + 1 WHERE user_id = ?")
-            .bind(id.into())
-            .execute(&self.pool)
-            .await?;
+    pub async fn inc_plot_user_id<I: Into<u64> + Copy>(&self, guild_id: u64, id: I) -> Result<()> {
+        let _timer = metrics::db_timer("inc_plot_user_id");
+        let res = sqlx::query(
+            "UPDATE plot_ids SET plot_inc = plot_inc + 1 WHERE guild_id = ? AND user_id = ?",
+        )
+        .bind(guild_id)
+        .bind(id.into())
+        .execute(&self.pool)
+        .await?;
 
         if res.rows_affected() == 0 {
-            sqlx::query("INSERT INTO plot_ids (user_id, plot_inc) VALUES (?, ?)")
+            sqlx::query("INSERT INTO plot_ids (guild_id, user_id, plot_inc) VALUES (?, ?, ?)")
+                .bind(guild_id)
                 .bind(id.into())
                 .bind(1)
                 .execute(&self.pool)
@@ -204,4 +854,75 @@ impl Database {
 
         Ok(())
     }
+
+    pub async fn get_profile_by_uuid(&self, uuid: &MinecraftUuid) -> Result<Option<Profile>> {
+        let _timer = metrics::db_timer("get_profile_by_uuid");
+        let mut rows =
+            sqlx::query("SELECT mc_name, fetched_at FROM profile_cache WHERE mc_uuid = ?")
+                .bind(uuid)
+                .fetch(&self.pool);
+
+        if let Some(row) = rows.try_next().await? {
+            let profile = Profile {
+                uuid: *uuid,
+                name: row.try_get("mc_name")?,
+                fetched_at: row.try_get("fetched_at")?,
+            };
+            Ok(Some(profile))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn get_profile_by_name(&self, name: &str) -> Result<Option<Profile>> {
+        let _timer = metrics::db_timer("get_profile_by_name");
+        let mut rows = sqlx::query(
+            "SELECT mc_uuid, mc_name, fetched_at FROM profile_cache WHERE mc_name = ? \
+             ORDER BY fetched_at DESC LIMIT 1",
+        )
+        .bind(name)
+        .fetch(&self.pool);
+
+        if let Some(row) = rows.try_next().await? {
+            let profile = Profile {
+                uuid: row.try_get("mc_uuid")?,
+                name: row.try_get("mc_name")?,
+                fetched_at: row.try_get("fetched_at")?,
+            };
+            Ok(Some(profile))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn set_profile(&self, profile: &Profile) -> Result<()> {
+        let _timer = metrics::db_timer("set_profile");
+        sqlx::query(
+            "INSERT INTO profile_cache (mc_uuid, mc_name, fetched_at) VALUES (?, ?, ?) \
+             ON DUPLICATE KEY UPDATE mc_name = VALUES(mc_name), fetched_at = VALUES(fetched_at)",
+        )
+        .bind(profile.uuid)
+        .bind(&profile.name)
+        .bind(profile.fetched_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+}
+
+fn region_from_row(row: &MySqlRow) -> Result<Region> {
+    Ok(Region {
+        guild_id: row.try_get("guild_id")?,
+        owner: row.try_get("user_id")?,
+        name: row.try_get("plot_id")?,
+        server: row.try_get("server")?,
+        world: row.try_get("world")?,
+        pending: row.try_get("pending")?,
+        display_name: row.try_get("display_name")?,
+        parent: row.try_get("parent_id")?,
+        perimeter: Perimeter(
+            Point(row.try_get("ax")?, row.try_get("az")?),
+            Point(row.try_get("bx")?, row.try_get("bz")?),
+        ),
+    })
 }
@@ -0,0 +1,175 @@
+use crate::conf;
+use crate::db::Database;
+use crate::idcache::IdCache;
+use crate::mc::{strip_formatting, Rcon, Servers};
+use crate::models::User;
+use crate::plots;
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use log::{error, info, warn};
+use serenity::http::Http;
+use serenity::model::prelude::UserId;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Periodically records which players are online and expires the
+/// plots of owners who have not been online for too long.
+pub async fn run(
+    cfg: conf::Expiry,
+    db: Arc<Database>,
+    rc: Arc<Servers>,
+    idcache: Arc<IdCache>,
+    http: Arc<Http>,
+    shutdown: Arc<Shutdown>,
+) {
+    let period = Duration::from_secs(cfg.interval_secs.max(1));
+    let mut interval = time::interval_at(Instant::now() + period, period);
+
+    loop {
+        interval.tick().await;
+
+        let Some(_guard) = shutdown.start() else {
+            return;
+        };
+
+        if let Err(err) = track(&db, &rc, &idcache).await {
+            error!("Tracking online players failed: {err}");
+        }
+
+        if let Err(err) = expire(&cfg, &db, &rc, &http).await {
+            error!("Expiring plots failed: {err}");
+        }
+    }
+}
+
+async fn track(db: &Database, rc: &Servers, idcache: &IdCache) -> Result<()> {
+    db.track_new_users().await?;
+
+    for server in rc.iter() {
+        let names = match online_players(server) {
+            Ok(names) => names,
+            Err(err) => {
+                // The other servers may still be reachable.
+                warn!("Listing players on {} failed: {err}", server.name());
+                continue;
+            }
+        };
+
+        for name in names {
+            match idcache.get_uuid_by_username(&name).await {
+                Ok(uuid) => db.touch_user(&uuid).await?,
+                Err(err) => warn!("Resolving online player {name} failed: {err}"),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn online_players(rc: &Rcon) -> Result<Vec<String>> {
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    let msg = conn
+        .cmd("list")
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    Ok(parse_list(&msg.body))
+}
+
+/// Extracts the player names from the response to `list`, like
+/// `There are 2 of a max of 20 players online: Steve, Alex`.
+fn parse_list(body: &str) -> Vec<String> {
+    let Some((_, players)) = body.split_once(':') else {
+        return vec![];
+    };
+
+    players
+        .split([',', '\n'])
+        .map(strip_formatting)
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+async fn expire(cfg: &conf::Expiry, db: &Database, rc: &Servers, http: &Http) -> Result<()> {
+    if cfg.warn_days > 0 {
+        let warn_after = cfg.inactive_days - cfg.warn_days;
+        for user in db.get_users_to_warn(warn_after).await? {
+            let msg = format!(
+                "You have not been online on the Minecraft server for {warn_after} days. \
+                 Your plots will expire in {} days unless you join the server. ⏰",
+                cfg.warn_days
+            );
+            notify(http, &user, &msg).await;
+            db.set_user_warned(&user.minecraft_uid).await?;
+        }
+    }
+
+    for user in db
+        .get_users_to_expire(cfg.inactive_days, cfg.warn_days)
+        .await?
+    {
+        let plots = db.get_user_plots(user.guild_id, user.discord_id).await?;
+
+        // Subregions expire along with their parent.
+        let mut expired = Vec::new();
+        for plot in plots.iter().filter(|p| p.parent.is_none()) {
+            match plots::expire(db, rc, plot).await {
+                Ok(()) => {
+                    info!("Plot {} of {} expired", plot.name, user.discord_id);
+                    if !plot.pending {
+                        expired.push(format!("`{}`", plot.name));
+                    }
+                }
+                Err(err) => error!("Expiring plot {} failed: {err}", plot.name),
+            }
+        }
+
+        if !expired.is_empty() {
+            let msg = format!(
+                "Your plots {} have expired as you have not been online for {} days. \
+                 You can restore them with `/region restore` within the next {} days.",
+                expired.join(", "),
+                cfg.inactive_days,
+                cfg.grace_days
+            );
+            notify(http, &user, &msg).await;
+        }
+    }
+
+    Ok(())
+}
+
+async fn notify(http: &Http, user: &User, msg: &str) {
+    let res = async {
+        let channel = UserId(user.discord_id).create_dm_channel(http).await?;
+        channel.say(http, msg).await?;
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(err) = res {
+        warn!("Notifying user {} failed: {err}", user.discord_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list() {
+        assert_eq!(
+            parse_list("There are 2 of a max of 20 players online: Steve, Alex"),
+            ["Steve", "Alex"]
+        );
+        assert_eq!(
+            parse_list("There are 1/20 players online:\n§eSteve§r"),
+            ["Steve"]
+        );
+        assert!(parse_list("There are 0 of a max of 20 players online: ").is_empty());
+    }
+}
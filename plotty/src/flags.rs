@@ -0,0 +1,102 @@
+use crate::conf::{Flag, FlagKind};
+use anyhow::Result;
+
+/// Returns the flag with the given name if owners may set it.
+pub fn find<'a>(flags: &'a [Flag], name: &str) -> Result<&'a Flag> {
+    flags
+        .iter()
+        .find(|f| f.name.eq_ignore_ascii_case(name))
+        .ok_or_else(|| anyhow::anyhow!("The flag `{name}` can not be set on plots."))
+}
+
+/// Checks the value against the kind of the flag and returns it
+/// in the form expected by WorldGuard.
+pub fn validate(flag: &Flag, value: &str) -> Result<String> {
+    let value = value.trim();
+
+    match flag.kind {
+        FlagKind::State => match value.to_lowercase().as_str() {
+            v @ ("allow" | "deny") => Ok(v.to_owned()),
+            _ => anyhow::bail!("The flag `{}` must be `allow` or `deny`.", flag.name),
+        },
+        FlagKind::Boolean => match value.to_lowercase().as_str() {
+            v @ ("true" | "false") => Ok(v.to_owned()),
+            _ => anyhow::bail!("The flag `{}` must be `true` or `false`.", flag.name),
+        },
+        FlagKind::String => {
+            if value.is_empty() {
+                anyhow::bail!("The flag `{}` must not be empty.", flag.name);
+            }
+            if value.chars().count() > flag.max_length {
+                anyhow::bail!(
+                    "The flag `{}` must not be longer than {} characters.",
+                    flag.name,
+                    flag.max_length
+                );
+            }
+            if value.chars().any(char::is_control) {
+                anyhow::bail!("The flag `{}` must be a single line.", flag.name);
+            }
+            Ok(value.to_owned())
+        }
+        FlagKind::Integer => {
+            let n: i64 = value
+                .parse()
+                .map_err(|_| anyhow::anyhow!("The flag `{}` must be a number.", flag.name))?;
+            if flag.min.is_some_and(|min| n < min) || flag.max.is_some_and(|max| n > max) {
+                anyhow::bail!(
+                    "The flag `{}` must be between {} and {}.",
+                    flag.name,
+                    flag.min.map_or("-∞".into(), |v| v.to_string()),
+                    flag.max.map_or("∞".into(), |v| v.to_string()),
+                );
+            }
+            Ok(n.to_string())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(kind: FlagKind) -> Flag {
+        Flag {
+            name: "test".into(),
+            kind,
+            max_length: 5,
+            min: Some(0),
+            max: Some(10),
+        }
+    }
+
+    #[test]
+    fn validate_values() {
+        let state = flag(FlagKind::State);
+        assert_eq!(validate(&state, "Allow").unwrap(), "allow");
+        assert!(validate(&state, "yes").is_err());
+
+        let boolean = flag(FlagKind::Boolean);
+        assert_eq!(validate(&boolean, "FALSE").unwrap(), "false");
+        assert!(validate(&boolean, "deny").is_err());
+
+        let string = flag(FlagKind::String);
+        assert_eq!(validate(&string, " hi ").unwrap(), "hi");
+        assert!(validate(&string, "").is_err());
+        assert!(validate(&string, "too long").is_err());
+        assert!(validate(&string, "a\nb").is_err());
+
+        let integer = flag(FlagKind::Integer);
+        assert_eq!(validate(&integer, "07").unwrap(), "7");
+        assert!(validate(&integer, "11").is_err());
+        assert!(validate(&integer, "-1").is_err());
+        assert!(validate(&integer, "x").is_err());
+    }
+
+    #[test]
+    fn find_flag() {
+        let flags = [flag(FlagKind::State)];
+        assert!(find(&flags, "TEST").is_ok());
+        assert!(find(&flags, "build").is_err());
+    }
+}
@@ -1,11 +1,15 @@
+use crate::mc::Servers;
 use crate::models::Region;
 use serenity::builder::CreateEmbed;
 use serenity::http::Http;
+use serenity::json::json;
 use serenity::json::Value;
+use serenity::model::prelude::autocomplete::AutocompleteInteraction;
 use serenity::model::prelude::interaction::application_command::{
     ApplicationCommandInteraction, CommandDataOption,
 };
 use serenity::model::prelude::Message;
+use serenity::prelude::Context;
 use serenity::utils::Color;
 use serenity::{async_trait, Result};
 use std::fmt;
@@ -97,8 +101,35 @@ impl OptionsHelper for CommandDataOption {
     }
 }
 
+/// Responds to the given autocomplete interaction with all
+/// servers starting with the value of the focused option.
+pub async fn autocomplete_servers(
+    ctx: &Context,
+    i: &AutocompleteInteraction,
+    rc: &Servers,
+    option: &CommandDataOption,
+) -> Result<()> {
+    let value = option
+        .value
+        .as_ref()
+        .and_then(|v| v.as_str())
+        .unwrap_or_default();
+
+    let servers = rc
+        .names()
+        .filter(|name| name.starts_with(value))
+        .map(|name| json!({ "name": name, "value": name }))
+        .collect();
+
+    i.create_autocomplete_response(&ctx.http, |r| r.set_choices(servers))
+        .await
+}
+
 impl fmt::Display for Region {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "`{}` ({}m²)", self.name, self.perimeter.size())
+        match &self.display_name {
+            Some(label) => write!(f, "`{label}` ({}, {}m²)", self.name, self.perimeter.size()),
+            None => write!(f, "`{}` ({}m²)", self.name, self.perimeter.size()),
+        }
     }
 }
@@ -1,48 +1,508 @@
+use crate::conf;
+use crate::db::Database;
+use crate::metrics;
+use crate::models::Profile;
+use crate::profiles::AccountProvider;
 use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
-use tokio::sync::OnceCell;
+use log::warn;
+use minecraft_uuid::{MinecraftUuid, ProfileProvider};
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 
-static CACHE: OnceCell<Arc<RwLock<HashMap<String, String>>>> = OnceCell::const_new();
+/// A username change detected by [`IdCache::refresh_usernames`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NameChange {
+    pub uuid: MinecraftUuid,
+    pub old: String,
+    pub new: String,
+}
+
+/// Caches resolved Minecraft profiles in front of a
+/// [`ProfileProvider`].
+///
+/// Profiles can be looked up by UUID or by case-insensitive
+/// name. Entries expire after the configured TTL and the least
+/// recently used entries are evicted once the capacity is
+/// exceeded. If a database is passed and the cache is persistent,
+/// profiles are also stored in the `profile_cache` table so that
+/// they survive restarts.
+///
+/// When the provider fails, expired entries are served instead
+/// of failing the lookup. Names of UUIDs which are not cached at
+/// all are taken from the bound users, as offline-mode accounts
+/// can not be resolved by any provider.
+pub struct IdCache<P = AccountProvider> {
+    provider: P,
+    db: Option<Arc<Database>>,
+    persistent: bool,
+    ttl_secs: u64,
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl<P: ProfileProvider> IdCache<P> {
+    pub fn new(provider: P, cfg: &conf::Cache, db: Option<Arc<Database>>) -> Self {
+        Self {
+            provider,
+            db,
+            persistent: cfg.persistent,
+            ttl_secs: cfg.ttl_secs,
+            capacity: cfg.capacity.max(1),
+            entries: Mutex::default(),
+        }
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    pub async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        let cached = self.entries().get(uuid);
+        let cached = match cached {
+            Some(p) if self.is_fresh(&p) => return Ok(hit(p.name)),
+            Some(p) => Some(p),
+            None => self.load(self.db_get_by_uuid(uuid).await),
+        };
+
+        if let Some(p) = cached.as_ref().filter(|p| self.is_fresh(p)) {
+            return Ok(hit(p.name.clone()));
+        }
+
+        let res = timed("username", self.provider.get_username_by_uuid(uuid)).await;
+        match res {
+            Ok(name) => {
+                self.set(*uuid, &name).await;
+                Ok(name)
+            }
+            Err(err) => match cached {
+                Some(p) => {
+                    warn!("Profile lookup for {uuid} failed, using stale name: {err}");
+                    Ok(p.name)
+                }
+                None => match self.db_get_bound_name(uuid).await {
+                    Some(name) => {
+                        self.set(*uuid, &name).await;
+                        Ok(name)
+                    }
+                    None => Err(err),
+                },
+            },
+        }
+    }
+
+    pub async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        let cached = self.entries().get_by_name(username);
+        let cached = match cached {
+            Some(p) if self.is_fresh(&p) => return Ok(hit(p.uuid)),
+            Some(p) => Some(p),
+            None => self.load(self.db_get_by_name(username).await),
+        };
+
+        if let Some(p) = cached.as_ref().filter(|p| self.is_fresh(p)) {
+            return Ok(hit(p.uuid));
+        }
+
+        let res = timed("uuid", self.provider.get_uuid_by_username(username)).await;
+        match res {
+            Ok(uuid) => {
+                self.set(uuid, username).await;
+                Ok(uuid)
+            }
+            Err(err) => match cached {
+                Some(p) => {
+                    warn!("Profile lookup for {username} failed, using stale UUID: {err}");
+                    Ok(p.uuid)
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    /// Returns the cached username of the given UUID without
+    /// resolving it, even if the entry has expired.
+    pub async fn peek_username(&self, uuid: &MinecraftUuid) -> Option<String> {
+        let cached = self.entries().get(uuid);
+        match cached {
+            Some(p) => Some(p.name),
+            None => self.load(self.db_get_by_uuid(uuid).await).map(|p| p.name),
+        }
+    }
+
+    /// Resolves the current usernames of the given UUIDs, updates
+    /// the cache and returns all names which have changed compared
+    /// to the cached ones.
+    ///
+    /// UUIDs which can not be resolved keep their cached name.
+    pub async fn refresh_usernames(&self, uuids: &[MinecraftUuid]) -> Vec<NameChange> {
+        let start = Instant::now();
+        let names = self.provider.get_usernames_by_uuids(uuids).await;
+        let failed = names.values().any(|res| res.is_err());
+        metrics::PROFILE_LOOKUP_DURATION
+            .with_label_values(&["usernames", if failed { "error" } else { "ok" }])
+            .observe(start.elapsed().as_secs_f64());
+
+        let mut changes = Vec::new();
+        for (uuid, res) in names {
+            let new = match res {
+                Ok(name) => name,
+                Err(err) => {
+                    warn!("Refreshing the name of {uuid} failed: {err}");
+                    continue;
+                }
+            };
+
+            let old = self.peek_username(&uuid).await;
+            self.set(uuid, &new).await;
 
-fn get_cache() -> &'static Arc<RwLock<HashMap<String, String>>> {
-    if !CACHE.initialized() {
-        CACHE
-            .set(Arc::new(RwLock::new(HashMap::new())))
-            .expect("setting cache map");
+            if let Some(old) = old.filter(|old| !old.eq_ignore_ascii_case(&new)) {
+                changes.push(NameChange { uuid, old, new });
+            }
+        }
+
+        changes
+    }
+
+    /// Stores the given profile in the cache and, if enabled,
+    /// in the database.
+    pub async fn set(&self, uuid: MinecraftUuid, name: &str) {
+        let profile = Profile {
+            uuid,
+            name: name.to_owned(),
+            fetched_at: now(),
+        };
+
+        self.entries().insert(profile.clone(), self.capacity);
+
+        if let Some(db) = self.db.as_ref().filter(|_| self.persistent) {
+            if let Err(err) = db.set_profile(&profile).await {
+                warn!("Persisting profile {uuid} failed: {err}");
+            }
+        }
+    }
+
+    fn entries(&self) -> std::sync::MutexGuard<'_, Entries> {
+        self.entries.lock().expect("cache map is poisoned")
+    }
+
+    fn is_fresh(&self, profile: &Profile) -> bool {
+        now().saturating_sub(profile.fetched_at) < self.ttl_secs
+    }
+
+    /// Puts a profile loaded from the database into the
+    /// in-memory cache.
+    fn load(&self, profile: Option<Profile>) -> Option<Profile> {
+        if let Some(p) = &profile {
+            self.entries().insert(p.clone(), self.capacity);
+        }
+        profile
+    }
+
+    async fn db_get_by_uuid(&self, uuid: &MinecraftUuid) -> Option<Profile> {
+        let db = self.db.as_ref().filter(|_| self.persistent)?;
+        db.get_profile_by_uuid(uuid)
+            .await
+            .inspect_err(|err| warn!("Loading cached profile {uuid} failed: {err}"))
+            .ok()
+            .flatten()
     }
 
-    CACHE.get().expect("getting cache map")
+    async fn db_get_by_name(&self, name: &str) -> Option<Profile> {
+        let db = self.db.as_ref().filter(|_| self.persistent)?;
+        db.get_profile_by_name(name)
+            .await
+            .inspect_err(|err| warn!("Loading cached profile {name} failed: {err}"))
+            .ok()
+            .flatten()
+    }
+
+    async fn db_get_bound_name(&self, uuid: &MinecraftUuid) -> Option<String> {
+        let db = self.db.as_ref()?;
+        db.get_bound_name(uuid)
+            .await
+            .inspect_err(|err| warn!("Loading bound name of {uuid} failed: {err}"))
+            .ok()
+            .flatten()
+    }
 }
 
-pub async fn get_username_by_uuid(uuid: &str) -> Result<String> {
-    {
-        let cache = get_cache().read().expect("cache map is poisoned");
-        if let Some(name) = cache.get(uuid) {
-            return Ok(name.to_owned());
+/// Counts a lookup answered by the cache.
+fn hit<T>(value: T) -> T {
+    metrics::PROFILE_CACHE.with_label_values(&["hit"]).inc();
+    value
+}
+
+/// Counts a lookup which has to be resolved by the provider and
+/// records its duration.
+async fn timed<T>(lookup: &str, f: impl Future<Output = Result<T>>) -> Result<T> {
+    metrics::PROFILE_CACHE.with_label_values(&["miss"]).inc();
+
+    let start = Instant::now();
+    let res = f.await;
+    metrics::PROFILE_LOOKUP_DURATION
+        .with_label_values(&[lookup, if res.is_ok() { "ok" } else { "error" }])
+        .observe(start.elapsed().as_secs_f64());
+
+    res
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+struct Entry {
+    name: String,
+    fetched_at: u64,
+    used: u64,
+}
+
+/// In-memory profile index with least recently used
+/// bookkeeping.
+#[derive(Default)]
+struct Entries {
+    by_uuid: HashMap<MinecraftUuid, Entry>,
+    by_name: HashMap<String, MinecraftUuid>,
+    lru: BTreeMap<u64, MinecraftUuid>,
+    tick: u64,
+}
+
+impl Entries {
+    fn get(&mut self, uuid: &MinecraftUuid) -> Option<Profile> {
+        let tick = self.next_tick();
+        let entry = self.by_uuid.get_mut(uuid)?;
+
+        self.lru.remove(&entry.used);
+        self.lru.insert(tick, *uuid);
+        entry.used = tick;
+
+        Some(Profile {
+            uuid: *uuid,
+            name: entry.name.clone(),
+            fetched_at: entry.fetched_at,
+        })
+    }
+
+    fn get_by_name(&mut self, name: &str) -> Option<Profile> {
+        let uuid = *self.by_name.get(&name.to_lowercase())?;
+        self.get(&uuid)
+    }
+
+    fn insert(&mut self, profile: Profile, capacity: usize) {
+        self.remove(&profile.uuid);
+
+        let tick = self.next_tick();
+        self.by_name
+            .insert(profile.name.to_lowercase(), profile.uuid);
+        self.lru.insert(tick, profile.uuid);
+        self.by_uuid.insert(
+            profile.uuid,
+            Entry {
+                name: profile.name,
+                fetched_at: profile.fetched_at,
+                used: tick,
+            },
+        );
+
+        while self.by_uuid.len() > capacity {
+            let Some((_, uuid)) = self.lru.pop_first() else {
+                break;
+            };
+            self.remove(&uuid);
         }
     }
 
-    let name = minecraft_uuid::get_username_by_uuid(uuid).await?;
+    fn remove(&mut self, uuid: &MinecraftUuid) {
+        let Some(entry) = self.by_uuid.remove(uuid) else {
+            return;
+        };
 
-    let mut cache = get_cache().write().expect("cache map is poisoned");
-    cache.insert(uuid.to_owned(), name.clone());
+        self.lru.remove(&entry.used);
 
-    Ok(name)
+        // The name might have been taken over by another
+        // account in the meantime.
+        let key = entry.name.to_lowercase();
+        if self.by_name.get(&key) == Some(uuid) {
+            self.by_name.remove(&key);
+        }
+    }
+
+    fn next_tick(&mut self) -> u64 {
+        self.tick += 1;
+        self.tick
+    }
 }
 
-pub async fn get_uuid_by_username(username: &str) -> Result<String> {
-    {
-        let cache = get_cache().read().expect("cache map is poisoned");
-        if let Some((_, uuid)) = cache.iter().find(|(_, v)| v == &username) {
-            return Ok(uuid.to_owned());
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serenity::async_trait;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    #[derive(Default)]
+    struct CountingProvider {
+        calls: AtomicUsize,
+        down: AtomicBool,
+    }
+
+    impl CountingProvider {
+        fn calls(&self) -> usize {
+            self.calls.load(Ordering::SeqCst)
+        }
+
+        fn check(&self) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.down.load(Ordering::SeqCst) {
+                anyhow::bail!("provider is down");
+            }
+            Ok(())
         }
     }
 
-    let uuid = minecraft_uuid::get_uuid_by_username(username).await?;
+    #[async_trait]
+    impl ProfileProvider for CountingProvider {
+        fn name(&self) -> &str {
+            "counting"
+        }
 
-    let mut cache = get_cache().write().expect("cache map is poisoned");
-    cache.insert(uuid.clone(), username.to_owned());
+        async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+            self.check()?;
+            Ok(MinecraftUuid::from_u128(username.len() as u128))
+        }
 
-    Ok(uuid)
+        async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+            self.check()?;
+            Ok(format!("Player{}", uuid.as_u128()))
+        }
+    }
+
+    fn cache(ttl_secs: u64, capacity: usize) -> IdCache<CountingProvider> {
+        let cfg = conf::Cache {
+            ttl_secs,
+            capacity,
+            persistent: false,
+        };
+        IdCache::new(CountingProvider::default(), &cfg, None)
+    }
+
+    #[tokio::test]
+    async fn lookups_are_cached_bidirectionally() {
+        let c = cache(60, 10);
+        let uuid = MinecraftUuid::from_u128(7);
+
+        assert_eq!(c.get_username_by_uuid(&uuid).await.unwrap(), "Player7");
+        assert_eq!(c.get_uuid_by_username("player7").await.unwrap(), uuid);
+        assert_eq!(c.get_uuid_by_username("PLAYER7").await.unwrap(), uuid);
+        assert_eq!(c.get_username_by_uuid(&uuid).await.unwrap(), "Player7");
+        assert_eq!(c.provider().calls(), 1);
+    }
+
+    #[tokio::test]
+    async fn expired_entries_are_refreshed() {
+        let c = cache(0, 10);
+        let uuid = MinecraftUuid::from_u128(7);
+
+        c.get_username_by_uuid(&uuid).await.unwrap();
+        c.get_username_by_uuid(&uuid).await.unwrap();
+        assert_eq!(c.provider().calls(), 2);
+
+        c.provider().down.store(true, Ordering::SeqCst);
+        assert_eq!(c.get_username_by_uuid(&uuid).await.unwrap(), "Player7");
+        assert!(c
+            .get_username_by_uuid(&MinecraftUuid::from_u128(8))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_entries_are_evicted() {
+        let c = cache(60, 2);
+        let (a, b, d) = (
+            MinecraftUuid::from_u128(1),
+            MinecraftUuid::from_u128(2),
+            MinecraftUuid::from_u128(3),
+        );
+
+        c.get_username_by_uuid(&a).await.unwrap();
+        c.get_username_by_uuid(&b).await.unwrap();
+        c.get_username_by_uuid(&a).await.unwrap();
+        c.get_username_by_uuid(&d).await.unwrap();
+        assert_eq!(c.provider().calls(), 3);
+
+        c.get_username_by_uuid(&a).await.unwrap();
+        assert_eq!(c.provider().calls(), 3);
+        c.get_username_by_uuid(&b).await.unwrap();
+        assert_eq!(c.provider().calls(), 4);
+        assert!(c.entries().get_by_name("player1").is_some());
+        assert!(c.entries().get_by_name("player3").is_none());
+    }
+
+    #[tokio::test]
+    async fn refresh_reports_changed_names() {
+        let c = cache(60, 10);
+        let (a, b, d) = (
+            MinecraftUuid::from_u128(1),
+            MinecraftUuid::from_u128(2),
+            MinecraftUuid::from_u128(3),
+        );
+
+        c.set(a, "Player1").await;
+        c.set(b, "OldName").await;
+
+        let changes = c.refresh_usernames(&[a, b, d]).await;
+        assert_eq!(
+            changes,
+            vec![NameChange {
+                uuid: b,
+                old: "OldName".into(),
+                new: "Player2".into(),
+            }]
+        );
+        assert_eq!(c.get_uuid_by_username("player2").await.unwrap(), b);
+        assert_eq!(c.peek_username(&d).await.unwrap(), "Player3");
+        assert_eq!(c.provider().calls(), 3);
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_names_which_can_not_be_resolved() {
+        let c = cache(60, 10);
+        let uuid = MinecraftUuid::from_u128(1);
+
+        c.set(uuid, "OldName").await;
+        c.provider().down.store(true, Ordering::SeqCst);
+
+        assert_eq!(c.refresh_usernames(&[uuid]).await, vec![]);
+        assert_eq!(c.peek_username(&uuid).await.unwrap(), "OldName");
+    }
+
+    #[test]
+    fn renamed_profiles_drop_their_old_name() {
+        let mut e = Entries::default();
+        let uuid = MinecraftUuid::from_u128(1);
+
+        e.insert(
+            Profile {
+                uuid,
+                name: "Old".into(),
+                fetched_at: 0,
+            },
+            10,
+        );
+        e.insert(
+            Profile {
+                uuid,
+                name: "New".into(),
+                fetched_at: 0,
+            },
+            10,
+        );
+
+        assert!(e.get_by_name("old").is_none());
+        assert_eq!(e.get_by_name("new").unwrap().uuid, uuid);
+        assert_eq!(e.by_uuid.len(), 1);
+        assert_eq!(e.lru.len(), 1);
+    }
 }
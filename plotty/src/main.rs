@@ -1,37 +1,69 @@
+mod api;
 mod commands;
 mod conf;
 mod db;
+mod expiry;
+mod flags;
 mod helpers;
 mod idcache;
 mod mc;
+mod metrics;
 mod models;
+mod namesync;
+mod plots;
+mod profiles;
+mod purge;
+mod quota;
+mod review;
+mod selection;
+mod shutdown;
+mod verification;
+mod wizard;
 
-use config::builder::DefaultState;
-use config::{Config, ConfigBuilder, Environment, File, FileFormat};
 use db::Database;
 use env_logger::Env;
-use log::{debug, error, info};
-use mc::Rcon;
+use idcache::IdCache;
+use log::{debug, error, info, warn};
+use mc::Servers;
+use profiles::AccountProvider;
 use serenity::builder::CreateEmbed;
 use serenity::model::application::interaction::application_command::ApplicationCommandInteraction;
 use serenity::model::application::interaction::autocomplete::AutocompleteInteraction;
+use serenity::model::application::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::command::Command;
 use serenity::model::prelude::interaction::{Interaction, InteractionResponseType};
 use serenity::model::prelude::{GuildId, Ready};
 use serenity::prelude::{Context, EventHandler, GatewayIntents};
 use serenity::utils::Color;
 use serenity::{async_trait, Client};
+use shutdown::Shutdown;
 use std::io;
 use std::sync::Arc;
+use std::time::Duration;
 
 struct Handler {
-    cfg: conf::Config,
+    cfg: Arc<conf::Shared>,
     db: Arc<Database>,
-    rc: Arc<Rcon>,
+    rc: Arc<Servers>,
+    idcache: Arc<IdCache>,
+    shutdown: Arc<Shutdown>,
 }
 
 impl Handler {
-    fn new(cfg: conf::Config, db: Arc<Database>, rc: Arc<Rcon>) -> Self {
-        Self { cfg, db, rc }
+    fn new(
+        cfg: Arc<conf::Shared>,
+        db: Arc<Database>,
+        rc: Arc<Servers>,
+        idcache: Arc<IdCache>,
+        shutdown: Arc<Shutdown>,
+    ) -> Self {
+        Self {
+            cfg,
+            db,
+            rc,
+            idcache,
+            shutdown,
+        }
     }
 }
 
@@ -41,6 +73,27 @@ impl Handler {
         ctx: Context,
         command: ApplicationCommandInteraction,
     ) {
+        let Some(_guard) = self.shutdown.start() else {
+            let res = command
+                .create_interaction_response(&ctx.http, |i| {
+                    i.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.ephemeral(true).add_embed(
+                                CreateEmbed::default()
+                                    .color(Color::ORANGE)
+                                    .description("plotty is restarting. Please try again shortly.")
+                                    .to_owned(),
+                            )
+                        })
+                })
+                .await;
+
+            if let Err(err) = res {
+                error!("Failed responding shutdown message: {}", err);
+            }
+            return;
+        };
+
         // Defer interaction emphemerally
         if let Err(err) = command
             .create_interaction_response(&ctx.http, |i| {
@@ -54,12 +107,16 @@ impl Handler {
         }
 
         debug!("Received command interaction: {:#?}", command);
-        let res = match command.data.name.as_str() {
-            "region" => commands::region::run(&ctx, &command, &self.db, &self.rc).await,
-            "bind" => commands::bind::run(&ctx, &command, &self.db, &self.rc).await,
-            _ => Err(anyhow::anyhow!("not implemented")),
+        let cfg = self.cfg.get();
+        let res = match command.guild_id.and_then(|id| cfg.guild(id.0)) {
+            Some(guild) => self.run_command(&ctx, &command, &cfg, &guild).await,
+            None => Err(anyhow::anyhow!("plotty is not available on this server.")),
         };
 
+        metrics::COMMANDS
+            .with_label_values(&[&command.data.name, if res.is_ok() { "ok" } else { "error" }])
+            .inc();
+
         if let Err(err) = res {
             let res = command
                 .create_followup_message(&ctx.http, |response| {
@@ -80,10 +137,121 @@ impl Handler {
         }
     }
 
+    async fn run_command(
+        &self,
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        cfg: &conf::Config,
+        guild: &conf::GuildSettings,
+    ) -> anyhow::Result<()> {
+        let rc = self.rc.only(&guild.servers);
+
+        match command.data.name.as_str() {
+            "region" => {
+                commands::region::run(
+                    ctx,
+                    command,
+                    cfg,
+                    &self.db,
+                    &rc,
+                    &self.idcache,
+                    guild,
+                    &self.shutdown,
+                )
+                .await
+            }
+            "bind" => {
+                commands::bind::run(
+                    ctx,
+                    command,
+                    &self.db,
+                    &rc,
+                    &self.idcache,
+                    guild,
+                    &cfg.verification,
+                    &self.shutdown,
+                )
+                .await
+            }
+            "unbind" => {
+                commands::unbind::run(
+                    ctx,
+                    command,
+                    &self.db,
+                    &rc,
+                    &self.idcache,
+                    guild,
+                    &self.shutdown,
+                )
+                .await
+            }
+            "whoami" => commands::whoami::run(ctx, command, &self.db, &self.idcache, guild).await,
+            "admin" => commands::admin::run(ctx, command, &self.cfg, &self.db, &rc, guild).await,
+            _ => Err(anyhow::anyhow!("not implemented")),
+        }
+    }
+
+    async fn handle_component(&self, ctx: Context, component: MessageComponentInteraction) {
+        // Other components are awaited by the commands which created them.
+        if !review::is_review(&component) {
+            return;
+        }
+
+        let Some(_guard) = self.shutdown.start() else {
+            return;
+        };
+
+        let cfg = self.cfg.get();
+        let res = match component.guild_id.and_then(|id| cfg.guild(id.0)) {
+            Some(guild) => {
+                let rc = self.rc.only(&guild.servers);
+                review::handle(&ctx, &component, &self.db, &rc, &self.idcache, &guild).await
+            }
+            None => Err(anyhow::anyhow!("plotty is not available on this server.")),
+        };
+
+        if let Err(err) = res {
+            let res = component
+                .create_followup_message(&ctx.http, |response| {
+                    response.ephemeral(true);
+                    response.add_embed(
+                        CreateEmbed::default()
+                            .color(Color::RED)
+                            .description("The review failed.")
+                            .field("Error", err.to_string(), false)
+                            .to_owned(),
+                    )
+                })
+                .await;
+
+            if let Err(err) = res {
+                error!("Failed responding review error message: {}", err);
+            }
+        }
+    }
+
     async fn handle_autocomplete(&self, ctx: Context, autocomplete: AutocompleteInteraction) {
+        let cfg = self.cfg.get();
+        let Some(guild) = autocomplete.guild_id.and_then(|id| cfg.guild(id.0)) else {
+            return;
+        };
+        let rc = self.rc.only(&guild.servers);
+
         let res = match autocomplete.data.name.as_str() {
-            "region" => commands::region::autocomplete(&ctx, &autocomplete, &self.db).await,
-            "bind" => Ok(()),
+            "region" => {
+                commands::region::autocomplete(
+                    &ctx,
+                    &autocomplete,
+                    &cfg,
+                    &self.db,
+                    &rc,
+                    &self.idcache,
+                    &guild,
+                )
+                .await
+            }
+            "bind" => commands::bind::autocomplete(&ctx, &autocomplete, &rc).await,
+            "admin" => commands::admin::autocomplete(&ctx, &autocomplete, &rc).await,
             _ => Ok(()),
         };
 
@@ -103,6 +271,7 @@ impl EventHandler for Handler {
             Interaction::Autocomplete(autocomplete) => {
                 self.handle_autocomplete(ctx, autocomplete).await
             }
+            Interaction::MessageComponent(component) => self.handle_component(ctx, component).await,
             _ => {}
         }
     }
@@ -110,15 +279,21 @@ impl EventHandler for Handler {
     async fn ready(&self, ctx: Context, ready: Ready) {
         info!("Client ready (logged in as {})", ready.user.tag());
 
-        let guild_id = GuildId(self.cfg.discord.guildid);
+        let cfg = self.cfg.get();
 
-        GuildId::set_application_commands(&guild_id, &ctx.http, |commands| {
-            commands
-                .create_application_command(|command| commands::region::register(command))
-                .create_application_command(|command| commands::bind::register(command))
-        })
-        .await
-        .expect("Command registration failed");
+        if cfg.discord.global_commands {
+            Command::set_global_application_commands(&ctx.http, commands::register)
+                .await
+                .expect("Command registration failed");
+            return;
+        }
+
+        for id in cfg.guild_ids() {
+            GuildId(id)
+                .set_application_commands(&ctx.http, commands::register)
+                .await
+                .expect("Command registration failed");
+        }
     }
 }
 
@@ -131,18 +306,9 @@ async fn main() {
     .map_err(io::Error::other)
     .expect("Failed building logger");
 
-    let mut b = Config::builder();
-
-    b = add_source_files_for_dir(b, "config");
-    b = add_source_files_for_dir(b, "dev.config");
-    b = b.add_source(Environment::with_prefix("PLOTTY").separator("_"));
-
     info!("Reading config ...");
-    let cfg: conf::Config = b
-        .build()
-        .expect("Failed to build config")
-        .try_deserialize()
-        .expect("Failed deserializing config");
+    let cfg = conf::Config::load().expect("Failed reading config");
+    cfg.validate().expect("Invalid config");
 
     info!("Initializing database ...");
     let db = Arc::new(
@@ -153,29 +319,122 @@ async fn main() {
 
     info!("Preparing database ...");
     db.init().await.expect("Database preparation failed");
+    if let Some(guild_id) = cfg.legacy_guild() {
+        db.adopt_unscoped(guild_id)
+            .await
+            .expect("Database preparation failed");
+    }
 
     info!("Initializing RCON connection ...");
-    let rc = Arc::new(Rcon::new(&cfg.rcon).expect("RCON client initialization failed"));
+    let rc = Arc::new(Servers::new(&cfg.rcon).expect("RCON client initialization failed"));
+
+    let idcache = Arc::new(IdCache::new(
+        AccountProvider::from_config(&cfg.profiles),
+        &cfg.cache,
+        Some(db.clone()),
+    ));
+
+    let shared_cfg = Arc::new(conf::Shared::new(cfg.clone()));
+    let shutdown = Arc::new(Shutdown::default());
 
     let mut client = Client::builder(cfg.discord.token.clone(), GatewayIntents::empty())
-        .event_handler(Handler::new(cfg.clone(), db.clone(), rc.clone()))
+        .event_handler(Handler::new(
+            shared_cfg.clone(),
+            db.clone(),
+            rc.clone(),
+            idcache.clone(),
+            shutdown.clone(),
+        ))
         .await
         .expect("Failed creating Discord client");
 
+    // Admin roles are checked before connecting, so that a typo can not
+    // leave the bot running without the intended admins.
+    cfg.validate_roles(&client.cache_and_http.http)
+        .await
+        .expect("Invalid config");
+
+    if cfg.namesync.enabled {
+        info!("Starting name sync ...");
+        tokio::spawn(namesync::run(
+            cfg.namesync.clone(),
+            db.clone(),
+            rc.clone(),
+            idcache.clone(),
+            client.cache_and_http.http.clone(),
+            shutdown.clone(),
+        ));
+    }
+
+    tokio::spawn(purge::run(shared_cfg.clone(), db.clone(), shutdown.clone()));
+
+    if cfg.expiry.enabled {
+        info!("Starting plot expiry ...");
+        tokio::spawn(expiry::run(
+            cfg.expiry.clone(),
+            db.clone(),
+            rc.clone(),
+            idcache.clone(),
+            client.cache_and_http.http.clone(),
+            shutdown.clone(),
+        ));
+    }
+
+    if cfg.api.enabled {
+        info!("Starting API on {} ...", cfg.api.address);
+        let state = api::AppState {
+            cfg: shared_cfg.clone(),
+            db: db.clone(),
+            rc: rc.clone(),
+            idcache: idcache.clone(),
+            shutdown: shutdown.clone(),
+            http: client.cache_and_http.http.clone(),
+        };
+        let address = cfg.api.address.clone();
+        tokio::spawn(async move {
+            if let Err(err) = api::serve(address, state).await {
+                error!("API server failed: {err}");
+            }
+        });
+    }
+
+    if cfg.metrics.enabled {
+        info!("Starting metrics on {} ...", cfg.metrics.address);
+        let state = metrics::AppState {
+            db: db.clone(),
+            rc: rc.clone(),
+            shard_manager: client.shard_manager.clone(),
+        };
+        let address = cfg.metrics.address.clone();
+        tokio::spawn(async move {
+            if let Err(err) = metrics::serve(address, state).await {
+                error!("Metrics server failed: {err}");
+            }
+        });
+    }
+
+    let shard_manager = client.shard_manager.clone();
+    let timeout = Duration::from_secs(cfg.shutdown.timeout_secs);
+    tokio::spawn(async move {
+        shutdown::signal().await;
+
+        info!("Shutting down ...");
+        if !shutdown.drain(timeout).await {
+            warn!(
+                "Shutting down with {} task(s) still running",
+                shutdown.running()
+            );
+        }
+
+        shard_manager.lock().await.shutdown_all().await;
+    });
+
     info!("Initializing Discord connection ...");
     client
         .start()
         .await
         .expect("Failed starting Discord client");
-}
 
-fn add_source_files_for_dir(
-    builder: ConfigBuilder<DefaultState>,
-    name: &str,
-) -> ConfigBuilder<DefaultState> {
-    builder
-        .add_source(File::new(&format!("{name}.yaml"), FileFormat::Yaml).required(false))
-        .add_source(File::new(&format!("{name}.yml"), FileFormat::Yaml).required(false))
-        .add_source(File::new(&format!("{name}.toml"), FileFormat::Toml).required(false))
-        .add_source(File::new(&format!("{name}.json"), FileFormat::Json5).required(false))
+    info!("Closing database connections ...");
+    db.close().await;
 }
@@ -1,9 +1,13 @@
 use crate::conf;
+use crate::metrics;
 use minecraft_client_rs::{Client, Message};
 use std::error;
 
 type Error = Box<dyn error::Error>;
 
+const ERR_PREFIX: &str = "§c";
+
+#[derive(Clone)]
 pub struct Rcon {
     cfg: conf::Rcon,
 }
@@ -13,14 +17,83 @@ impl Rcon {
         Ok(Self { cfg: cfg.clone() })
     }
 
+    /// The name of the server as configured.
+    pub fn name(&self) -> &str {
+        &self.cfg.name
+    }
+
     pub fn get_conn(&self) -> Result<Conn, Error> {
-        let mut client = Client::new(self.cfg.address.to_owned())?;
-        client.authenticate(self.cfg.password.to_owned())?;
-        Ok(Conn(client))
+        let connect = || -> Result<Client, Error> {
+            let mut client = Client::new(self.cfg.address.to_owned())?;
+            client.authenticate(self.cfg.password.to_owned())?;
+            Ok(client)
+        };
+
+        match connect() {
+            Ok(client) => Ok(Conn(client, self.cfg.name.clone())),
+            Err(err) => {
+                metrics::RCON_ERRORS.with_label_values(&[self.name()]).inc();
+                Err(err)
+            }
+        }
+    }
+}
+
+/// All configured Minecraft servers.
+pub struct Servers(Vec<Rcon>);
+
+impl Servers {
+    pub fn new(cfg: &[conf::Rcon]) -> Result<Self, Error> {
+        let servers = cfg.iter().map(Rcon::new).collect::<Result<_, _>>()?;
+        Ok(Self(servers))
+    }
+
+    /// Returns only the servers with the given names,
+    /// or all servers if no names are given.
+    pub fn only(&self, names: &[String]) -> Self {
+        if names.is_empty() {
+            return Self(self.0.clone());
+        }
+        Self(
+            self.0
+                .iter()
+                .filter(|s| names.iter().any(|n| n == s.name()))
+                .cloned()
+                .collect(),
+        )
+    }
+
+    /// Returns the server with the given name.
+    pub fn get(&self, name: &str) -> anyhow::Result<&Rcon> {
+        self.0
+            .iter()
+            .find(|s| s.name() == name)
+            .ok_or_else(|| anyhow::anyhow!("There is no server named {name}."))
+    }
+
+    /// Returns the server with the given name or the
+    /// first configured server if no name is given.
+    pub fn get_or_default(&self, name: Option<&str>) -> anyhow::Result<&Rcon> {
+        match name {
+            Some(name) => self.get(name),
+            None => self
+                .0
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("No server configured.")),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Rcon> {
+        self.0.iter()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(|s| s.name())
     }
 }
 
-pub struct Conn(Client);
+/// A connection to the server with the given name.
+pub struct Conn(Client, String);
 
 impl Drop for Conn {
     fn drop(&mut self) {
@@ -33,6 +106,39 @@ impl Drop for Conn {
 
 impl Conn {
     pub fn cmd(&mut self, cmd: &str) -> Result<Message, Error> {
-        self.0.send_command(cmd.to_owned())
+        let timer = metrics::RCON_DURATION
+            .with_label_values(&[&self.1])
+            .start_timer();
+        let res = self.0.send_command(cmd.to_owned());
+        timer.observe_duration();
+
+        if res.is_err() {
+            metrics::RCON_ERRORS.with_label_values(&[&self.1]).inc();
+        }
+        res
+    }
+}
+
+/// Turns failed commands, which are indicated by responses
+/// starting with the red color code, into errors.
+pub fn check_err(res: Result<Message, Error>) -> anyhow::Result<Message> {
+    let msg = res.map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    if msg.body.starts_with(ERR_PREFIX) {
+        anyhow::bail!(msg.body);
+    }
+    Ok(msg)
+}
+
+/// Removes `§` formatting codes from a response.
+pub fn strip_formatting(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            res.push(c);
+        }
     }
+    res
 }
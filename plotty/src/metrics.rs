@@ -0,0 +1,228 @@
+//! Prometheus metrics and the health check endpoint.
+
+use crate::db::Database;
+use crate::mc::Servers;
+use axum::extract::State;
+use axum::http::{header, StatusCode};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use log::error;
+use prometheus::{
+    Encoder, HistogramOpts, HistogramTimer, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use serde::Serialize;
+use serenity::client::bridge::gateway::ShardManager;
+use serenity::gateway::ConnectionStage;
+use serenity::prelude::Mutex;
+use std::collections::BTreeMap;
+use std::sync::{Arc, LazyLock};
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::new);
+
+pub static COMMANDS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "plotty_commands_total",
+            "Command invocations by name and outcome.",
+        ),
+        &["command", "outcome"],
+    ))
+});
+
+pub static RCON_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new("plotty_rcon_duration_seconds", "Duration of RCON commands."),
+        &["server"],
+    ))
+});
+
+pub static RCON_ERRORS: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "plotty_rcon_errors_total",
+            "Failed RCON connections and commands.",
+        ),
+        &["server"],
+    ))
+});
+
+pub static PROFILE_LOOKUP_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "plotty_profile_lookup_duration_seconds",
+            "Duration of profile lookups which were not answered by the cache.",
+        ),
+        &["lookup", "outcome"],
+    ))
+});
+
+pub static PROFILE_CACHE: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(IntCounterVec::new(
+        Opts::new(
+            "plotty_profile_cache_requests_total",
+            "Profile cache requests by result.",
+        ),
+        &["result"],
+    ))
+});
+
+pub static DB_QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(HistogramVec::new(
+        HistogramOpts::new(
+            "plotty_db_query_duration_seconds",
+            "Duration of database queries.",
+        ),
+        &["query"],
+    ))
+});
+
+pub static PLOTS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(IntGaugeVec::new(
+        Opts::new("plotty_plots", "Number of plots by server."),
+        &["server"],
+    ))
+});
+
+fn register<M: prometheus::core::Collector + Clone + 'static>(metric: prometheus::Result<M>) -> M {
+    let metric = metric.expect("invalid metric");
+    REGISTRY
+        .register(Box::new(metric.clone()))
+        .expect("metric registered twice");
+    metric
+}
+
+/// Starts a timer which records the duration of a database query
+/// when dropped.
+pub fn db_timer(query: &str) -> HistogramTimer {
+    DB_QUERY_DURATION.with_label_values(&[query]).start_timer()
+}
+
+#[derive(Clone)]
+pub struct AppState {
+    pub db: Arc<Database>,
+    pub rc: Arc<Servers>,
+    pub shard_manager: Arc<Mutex<ShardManager>>,
+}
+
+pub fn router(state: AppState) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics))
+        .route("/healthz", get(healthz))
+        .with_state(state)
+}
+
+pub async fn serve(address: String, state: AppState) -> anyhow::Result<()> {
+    let listener = tokio::net::TcpListener::bind(&address).await?;
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+async fn metrics(State(state): State<AppState>) -> impl IntoResponse {
+    // Plot totals are taken from the database on every scrape
+    // so that they can not drift.
+    match state.db.get_plots().await {
+        Ok(plots) => {
+            PLOTS.reset();
+            for plot in plots {
+                PLOTS.with_label_values(&[&plot.server]).inc();
+            }
+        }
+        Err(err) => error!("Counting plots failed: {err}"),
+    }
+
+    let mut buf = Vec::new();
+    if let Err(err) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buf) {
+        error!("Encoding metrics failed: {err}");
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    }
+
+    ([(header::CONTENT_TYPE, prometheus::TEXT_FORMAT)], buf).into_response()
+}
+
+#[derive(Serialize)]
+struct Health {
+    healthy: bool,
+    /// The error of every failed check, or `ok`.
+    checks: BTreeMap<String, String>,
+}
+
+async fn healthz(State(state): State<AppState>) -> impl IntoResponse {
+    let mut checks = BTreeMap::new();
+
+    checks.insert("database".to_owned(), outcome(state.db.ping().await));
+
+    for rcon in state.rc.iter() {
+        let rcon = rcon.clone();
+        let name = format!("rcon:{}", rcon.name());
+        // Connecting blocks until the server responds or the
+        // connection times out.
+        let res = tokio::task::spawn_blocking(move || {
+            rcon.get_conn()
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("{e}"))
+        })
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|r| r);
+        checks.insert(name, outcome(res));
+    }
+
+    checks.insert(
+        "gateway".to_owned(),
+        outcome(gateway(&state.shard_manager).await),
+    );
+
+    let healthy = checks.values().all(|c| c == "ok");
+    let status = match healthy {
+        true => StatusCode::OK,
+        false => StatusCode::SERVICE_UNAVAILABLE,
+    };
+
+    (status, Json(Health { healthy, checks }))
+}
+
+async fn gateway(shard_manager: &Mutex<ShardManager>) -> anyhow::Result<()> {
+    let manager = shard_manager.lock().await;
+    let runners = manager.runners.lock().await;
+
+    if runners.is_empty() {
+        anyhow::bail!("no shards running");
+    }
+
+    for (id, runner) in runners.iter() {
+        if runner.stage != ConnectionStage::Connected {
+            anyhow::bail!("shard {} is {}", id.0, runner.stage);
+        }
+    }
+
+    Ok(())
+}
+
+fn outcome(res: anyhow::Result<()>) -> String {
+    match res {
+        Ok(()) => "ok".to_owned(),
+        Err(err) => err.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gather() {
+        COMMANDS.with_label_values(&["region", "ok"]).inc();
+        db_timer("test").observe_duration();
+
+        let mut buf = Vec::new();
+        TextEncoder::new()
+            .encode(&REGISTRY.gather(), &mut buf)
+            .unwrap();
+        let text = String::from_utf8(buf).unwrap();
+
+        assert!(text.contains(r#"plotty_commands_total{command="region",outcome="ok"}"#));
+        assert!(text.contains(r#"plotty_db_query_duration_seconds_count{query="test"} 1"#));
+    }
+}
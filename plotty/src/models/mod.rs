@@ -3,3 +3,9 @@ pub use region::*;
 
 mod user;
 pub use user::*;
+
+mod profile;
+pub use profile::*;
+
+mod zone;
+pub use zone::*;
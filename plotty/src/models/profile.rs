@@ -0,0 +1,10 @@
+use minecraft_uuid::MinecraftUuid;
+
+#[derive(Clone, Debug)]
+pub struct Profile {
+    pub uuid: MinecraftUuid,
+    pub name: String,
+    /// Unix timestamp in seconds of when the profile
+    /// has been resolved.
+    pub fetched_at: u64,
+}
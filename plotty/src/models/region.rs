@@ -8,12 +8,43 @@ pub struct Perimeter(pub Point, pub Point);
 pub struct Region {
     pub perimeter: Perimeter,
     pub name: String,
-    pub owner: u64,
+    /// The Discord guild the plot has been created in.
+    pub guild_id: u64,
+    /// The Discord ID of the owner. Plots of unbound
+    /// users may be kept without any owner.
+    pub owner: Option<u64>,
+    /// The name of the server the plot is located on.
+    pub server: String,
+    /// The world the plot is located in.
+    pub world: String,
+    /// Whether the plot is waiting for staff approval.
+    pub pending: bool,
+    /// The name chosen by the owner, if any.
+    pub display_name: Option<String>,
+    /// The plot this region is a subregion of.
+    pub parent: Option<String>,
+}
+
+impl Region {
+    /// The display name of the plot or its region ID.
+    pub fn label(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// A previous perimeter of a plot.
+#[derive(Clone, Debug)]
+pub struct Revision {
+    pub number: u32,
+    pub world: String,
+    pub perimeter: Perimeter,
+    /// When the perimeter has been replaced, as Unix timestamp.
+    pub created_at: i64,
 }
 
 impl Perimeter {
     pub fn size(&self) -> i64 {
-        (self.0 .1 - self.0 .0) * (self.1 .1 - self.1 .0).abs()
+        ((self.1 .0 - self.0 .0) * (self.1 .1 - self.0 .1)).abs()
     }
 
     pub fn normalize(&self) -> Perimeter {
@@ -36,6 +67,24 @@ impl Perimeter {
         n.0 .0 < p.0 && n.1 .0 > p.0 && n.0 .1 < p.1 && n.1 .1 > p.1
     }
 
+    /// Whether the other perimeter lies entirely within this one.
+    /// Shared edges are allowed.
+    pub fn contains(&self, other: &Perimeter) -> bool {
+        let (n, o) = (self.normalize(), other.normalize());
+
+        n.0 .0 <= o.0 .0 && o.1 .0 <= n.1 .0 && n.0 .1 <= o.0 .1 && o.1 .1 <= n.1 .1
+    }
+
+    /// The distance between the closest edges of both perimeters,
+    /// rounded to full blocks. Zero if they touch or overlap.
+    pub fn distance(&self, other: &Perimeter) -> i64 {
+        let (n, o) = (self.normalize(), other.normalize());
+        let dx = (o.0 .0 - n.1 .0).max(n.0 .0 - o.1 .0).max(0);
+        let dz = (o.0 .1 - n.1 .1).max(n.0 .1 - o.1 .1).max(0);
+
+        ((dx * dx + dz * dz) as f64).sqrt().round() as i64
+    }
+
     pub fn intersects(&self, other: &Perimeter) -> bool {
         self.intersects_unidirect(other) || other.intersects_unidirect(self)
     }
@@ -92,6 +141,14 @@ mod tests {
         assert!(!p.contains_point(&Point(4, 3)));
     }
 
+    #[test]
+    fn perimeter_size() {
+        assert_eq!(Perimeter(Point(1, 2), Point(4, 5)).size(), 9);
+        assert_eq!(Perimeter(Point(4, 2), Point(1, 5)).size(), 9);
+        assert_eq!(Perimeter(Point(-10, 0), Point(10, 5)).size(), 100);
+        assert_eq!(Perimeter(Point(1, 2), Point(1, 5)).size(), 0);
+    }
+
     #[test]
     fn perimeter_intersects() {
         let p = Perimeter(Point(1, 2), Point(4, 5));
@@ -120,4 +177,27 @@ mod tests {
         assert!(!p.intersects(&o));
         assert!(!o.intersects(&p));
     }
+
+    #[test]
+    fn perimeter_contains() {
+        let p = Perimeter(Point(0, 0), Point(10, 10));
+
+        assert!(p.contains(&Perimeter(Point(2, 2), Point(5, 5))));
+        assert!(p.contains(&Perimeter(Point(10, 10), Point(0, 0))));
+        assert!(p.contains(&Perimeter(Point(0, 3), Point(4, 10))));
+        assert!(!p.contains(&Perimeter(Point(5, 5), Point(11, 8))));
+        assert!(!p.contains(&Perimeter(Point(-1, -1), Point(11, 11))));
+        assert!(!p.contains(&Perimeter(Point(20, 20), Point(30, 30))));
+    }
+
+    #[test]
+    fn perimeter_distance() {
+        let p = Perimeter(Point(0, 0), Point(10, 10));
+
+        assert_eq!(p.distance(&Perimeter(Point(15, 0), Point(20, 10))), 5);
+        assert_eq!(p.distance(&Perimeter(Point(-8, -4), Point(-5, -1))), 5);
+        assert_eq!(p.distance(&Perimeter(Point(13, 14), Point(20, 20))), 5);
+        assert_eq!(p.distance(&Perimeter(Point(10, 0), Point(20, 10))), 0);
+        assert_eq!(p.distance(&Perimeter(Point(5, 5), Point(20, 20))), 0);
+    }
 }
@@ -1,5 +1,8 @@
+use minecraft_uuid::MinecraftUuid;
+
 #[derive(Clone, Debug)]
 pub struct User {
+    pub guild_id: u64,
     pub discord_id: u64,
-    pub minecraft_uid: String,
+    pub minecraft_uid: MinecraftUuid,
 }
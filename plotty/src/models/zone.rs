@@ -0,0 +1,188 @@
+use super::{Perimeter, Point};
+use anyhow::Result;
+
+/// An area managed by admins in which plots may not be claimed
+/// freely, like the spawn or shared farms.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Zone {
+    pub name: String,
+    /// The name of the server the zone is located on.
+    pub server: String,
+    pub world: String,
+    /// The corners of the zone. Two corners span a rectangle,
+    /// more corners a polygon.
+    pub points: Vec<Point>,
+    /// Whether plots may be claimed in the zone once approved
+    /// by the staff instead of not at all.
+    pub approval: bool,
+}
+
+impl Zone {
+    /// Parses corners given as `x,z` pairs separated by spaces or
+    /// semicolons, like `0,0 100,100` for a rectangle.
+    pub fn parse_points(s: &str) -> Result<Vec<Point>> {
+        let points = s
+            .split([' ', ';'])
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let (x, z) = p
+                    .split_once(',')
+                    .ok_or_else(|| anyhow::anyhow!("`{p}` is not a corner like `x,z`."))?;
+                let coord = |v: &str| {
+                    v.trim()
+                        .parse::<i64>()
+                        .map_err(|_| anyhow::anyhow!("`{p}` is not a corner like `x,z`."))
+                };
+                Ok(Point(coord(x)?, coord(z)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        match points.as_slice() {
+            [a, b] if a.0 == b.0 || a.1 == b.1 => {
+                anyhow::bail!("The corners of a rectangle must span an area.")
+            }
+            [_, _, ..] => Ok(points),
+            _ => anyhow::bail!("A zone needs at least two corners."),
+        }
+    }
+
+    /// The corners of the zone as polygon.
+    pub fn polygon(&self) -> Vec<Point> {
+        match self.points.as_slice() {
+            [a, b] => vec![a.clone(), Point(b.0, a.1), b.clone(), Point(a.0, b.1)],
+            points => points.to_vec(),
+        }
+    }
+
+    /// Whether the perimeter overlaps the zone. Touching edges
+    /// are no overlap, like for plots.
+    pub fn intersects(&self, perimeter: &Perimeter) -> bool {
+        let polygon = self.polygon();
+        let p = perimeter.normalize();
+        if p.size() == 0 {
+            return false;
+        }
+
+        // An edge of the zone running through the perimeter.
+        if edges(&polygon).any(|(a, b)| enters(a, b, &p)) {
+            return true;
+        }
+
+        // Otherwise the perimeter can only lie entirely in the zone.
+        let center = (
+            (p.0 .0 + p.1 .0) as f64 / 2.0,
+            (p.0 .1 + p.1 .1) as f64 / 2.0,
+        );
+        contains(&polygon, center)
+    }
+}
+
+fn edges(polygon: &[Point]) -> impl Iterator<Item = (&Point, &Point)> {
+    polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .take(polygon.len())
+}
+
+/// Whether the segment a-b passes through the inside of the
+/// normalized perimeter, clipping it with Liang-Barsky.
+fn enters(a: &Point, b: &Point, p: &Perimeter) -> bool {
+    let (x0, z0) = (a.0 as f64, a.1 as f64);
+    let (dx, dz) = ((b.0 - a.0) as f64, (b.1 - a.1) as f64);
+    let (min_x, min_z) = (p.0 .0 as f64, p.0 .1 as f64);
+    let (max_x, max_z) = (p.1 .0 as f64, p.1 .1 as f64);
+
+    let mut t0: f64 = 0.0;
+    let mut t1: f64 = 1.0;
+    for (q, d) in [
+        (x0 - min_x, -dx),
+        (max_x - x0, dx),
+        (z0 - min_z, -dz),
+        (max_z - z0, dz),
+    ] {
+        if d == 0.0 {
+            if q < 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / d;
+            if d < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+
+    if t0 >= t1 {
+        return false;
+    }
+
+    // The clipped segment lies within the perimeter, so it passes
+    // through the inside unless it runs along the border.
+    let t = (t0 + t1) / 2.0;
+    let (x, z) = (x0 + t * dx, z0 + t * dz);
+    min_x < x && x < max_x && min_z < z && z < max_z
+}
+
+/// Whether the point lies inside the polygon, using ray casting.
+fn contains(polygon: &[Point], (x, z): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (a, b) in edges(polygon) {
+        let (ax, az, bx, bz) = (a.0 as f64, a.1 as f64, b.0 as f64, b.1 as f64);
+        if (az > z) != (bz > z) && x < ax + (z - az) * (bx - ax) / (bz - az) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(points: Vec<Point>) -> Zone {
+        Zone {
+            name: "spawn".into(),
+            server: "default".into(),
+            world: "world".into(),
+            points,
+            approval: false,
+        }
+    }
+
+    #[test]
+    fn parse_points() {
+        assert_eq!(
+            Zone::parse_points("0,0 10,-10").unwrap(),
+            [Point(0, 0), Point(10, -10)]
+        );
+        assert_eq!(Zone::parse_points("0,0;10,0; 5,5").unwrap().len(), 3);
+        assert!(Zone::parse_points("0,0").is_err());
+        assert!(Zone::parse_points("0,0 0,10").is_err());
+        assert!(Zone::parse_points("0,0 x,10").is_err());
+    }
+
+    #[test]
+    fn rectangle_intersects() {
+        let z = zone(vec![Point(0, 0), Point(10, 10)]);
+
+        assert!(z.intersects(&Perimeter(Point(5, 5), Point(15, 15))));
+        assert!(z.intersects(&Perimeter(Point(2, 2), Point(3, 3))));
+        assert!(z.intersects(&Perimeter(Point(-5, -5), Point(15, 15))));
+        assert!(z.intersects(&Perimeter(Point(-5, 4), Point(15, 6))));
+        assert!(!z.intersects(&Perimeter(Point(10, 0), Point(20, 10))));
+        assert!(!z.intersects(&Perimeter(Point(11, 11), Point(20, 20))));
+    }
+
+    #[test]
+    fn polygon_intersects() {
+        // A triangle below the diagonal from (0, 0) to (10, 10).
+        let z = zone(vec![Point(0, 0), Point(10, 0), Point(10, 10)]);
+
+        assert!(z.intersects(&Perimeter(Point(6, 1), Point(9, 3))));
+        assert!(z.intersects(&Perimeter(Point(4, 4), Point(6, 6))));
+        assert!(!z.intersects(&Perimeter(Point(0, 6), Point(3, 10))));
+        assert!(!z.intersects(&Perimeter(Point(0, 0), Point(-5, 5))));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::conf;
+use crate::db::Database;
+use crate::idcache::{IdCache, NameChange};
+use crate::mc::{Rcon, Servers};
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use log::{error, info};
+use serenity::http::Http;
+use serenity::model::prelude::UserId;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Periodically checks all bound users for Minecraft name
+/// changes and moves their whitelist entries to the new name.
+pub async fn run(
+    cfg: conf::NameSync,
+    db: Arc<Database>,
+    rc: Arc<Servers>,
+    idcache: Arc<IdCache>,
+    http: Arc<Http>,
+    shutdown: Arc<Shutdown>,
+) {
+    let period = Duration::from_secs(cfg.interval_secs.max(1));
+    let mut interval = time::interval_at(Instant::now() + period, period);
+
+    loop {
+        interval.tick().await;
+
+        let Some(_guard) = shutdown.start() else {
+            return;
+        };
+
+        if let Err(err) = sync(&cfg, &db, &rc, &idcache, &http).await {
+            error!("Name sync failed: {err}");
+        }
+    }
+}
+
+async fn sync(
+    cfg: &conf::NameSync,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    http: &Http,
+) -> Result<()> {
+    let users = db.list_users().await?;
+    let uuids: Vec<_> = users.iter().map(|u| u.minecraft_uid).collect();
+
+    let changes = idcache.refresh_usernames(&uuids).await;
+
+    for change in changes {
+        info!(
+            "Detected name change of {}: {} -> {}",
+            change.uuid, change.old, change.new
+        );
+        db.set_bound_name(&change.uuid, &change.new).await?;
+
+        for user in users.iter().filter(|u| u.minecraft_uid == change.uuid) {
+            for server in db.get_user_servers(user.guild_id, user.discord_id).await? {
+                if let Err(err) = rc
+                    .get(&server)
+                    .and_then(|rc| update_whitelist(rc, idcache, &change))
+                {
+                    error!(
+                        "Updating whitelist for {} on server {server} failed: {err}",
+                        change.uuid
+                    );
+                }
+            }
+
+            if !cfg.notify {
+                continue;
+            }
+
+            if let Err(err) = notify(http, user.discord_id, &change).await {
+                error!("Notifying user {} failed: {err}", user.discord_id);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn update_whitelist(rc: &Rcon, idcache: &IdCache, change: &NameChange) -> Result<()> {
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    let accounts = idcache.provider();
+    conn.cmd(&accounts.whitelist_command("remove", &change.old))
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    conn.cmd(&accounts.whitelist_command("add", &change.new))
+        .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+
+    Ok(())
+}
+
+async fn notify(http: &Http, discord_id: u64, change: &NameChange) -> Result<()> {
+    let channel = UserId(discord_id).create_dm_channel(http).await?;
+    channel
+        .say(
+            http,
+            format!(
+                concat!(
+                    "Looks like you have changed your Minecraft name from {} to {}. 👀\n\n",
+                    "Don't worry, your whitelist entry has been moved to your new name."
+                ),
+                change.old, change.new
+            ),
+        )
+        .await?;
+    Ok(())
+}
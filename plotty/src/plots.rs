@@ -0,0 +1,719 @@
+//! Plot management shared by the `/region` command and the API.
+
+use crate::conf;
+use crate::db::Database;
+use crate::flags;
+use crate::idcache::IdCache;
+use crate::mc::{check_err, Conn, Rcon, Servers};
+use crate::models::{Perimeter, Region, Zone};
+use crate::quota::{self, Usage};
+use anyhow::Result;
+use std::fmt;
+
+/// The WorldGuard priority of subregions, so that their members and
+/// flags take precedence over the ones of the parent plot.
+const SUBREGION_PRIORITY: i32 = 10;
+
+/// An error caused by the request itself rather than by a failing
+/// server or database, like a collision or an exceeded quota.
+#[derive(Debug)]
+pub struct Rejected(pub String);
+
+impl fmt::Display for Rejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Rejected {}
+
+/// Returns the plot with the given ID or display name if it belongs
+/// to the user in the given guild.
+pub async fn find_owned(
+    db: &Database,
+    guild: &conf::GuildSettings,
+    user_id: u64,
+    plot_name: &str,
+) -> Result<Option<Region>> {
+    let res = db
+        .find_plot(guild.id, plot_name)
+        .await?
+        .filter(|r| r.owner == Some(user_id));
+    Ok(res)
+}
+
+/// Creates a new plot for the user on the given server, or on the
+/// default server if none is given.
+///
+/// Plots which need to be approved are only stored as pending and
+/// have to be passed to [`crate::review::request`] by the caller.
+#[allow(clippy::too_many_arguments)]
+pub async fn create(
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+    user_id: u64,
+    username: &str,
+    server: Option<&str>,
+    world: &str,
+    perimeter: Perimeter,
+) -> Result<Region> {
+    let server = rc
+        .get_or_default(server)
+        .map_err(|e| Rejected(e.to_string()))?;
+
+    let mut plot_id = db
+        .get_plot_user_id(guild.id, user_id)
+        .await?
+        .unwrap_or_default();
+
+    // Plots kept from a previous binding may already use the next name.
+    let mut plot_name = format!("{}_plot_{}", username.replace('_', ""), plot_id + 1);
+    while name_taken(db, guild.id, &plot_name).await? {
+        db.inc_plot_user_id(guild.id, user_id).await?;
+        plot_id += 1;
+        plot_name = format!("{}_plot_{}", username.replace('_', ""), plot_id + 1);
+    }
+
+    let usage = Usage::of(&db.get_user_plots(guild.id, user_id).await?);
+    quota::check(&guild.quota, usage, 1, perimeter.size()).map_err(|e| Rejected(e.to_string()))?;
+
+    let zones = check_collisions(db, guild, user_id, server.name(), world, &perimeter).await?;
+
+    let region = Region {
+        owner: Some(user_id),
+        name: plot_name,
+        guild_id: guild.id,
+        pending: guild.approval.required(&perimeter) || !zones.is_empty(),
+        display_name: None,
+        parent: None,
+        perimeter,
+        server: server.name().to_owned(),
+        world: world.to_owned(),
+    };
+
+    db.inc_plot_user_id(guild.id, user_id).await?;
+    if !region.pending {
+        create_plot(server, &region, username)?;
+    }
+    db.add_plot(&region).await?;
+
+    Ok(region)
+}
+
+/// Creates a subregion inside the given plot, which can have its
+/// own members and flags. Subregions do not count towards the quota.
+pub async fn create_subregion(
+    db: &Database,
+    rc: &Servers,
+    parent: &Region,
+    username: &str,
+    perimeter: Perimeter,
+) -> Result<Region> {
+    check_active(parent)?;
+    if parent.parent.is_some() {
+        return Err(Rejected("Subregions can not contain further subregions.".into()).into());
+    }
+    if !parent.perimeter.contains(&perimeter) {
+        return Err(Rejected(format!(
+            "The subregion must lie entirely inside your plot `{}`!",
+            parent.label()
+        ))
+        .into());
+    }
+
+    let server = rc.get(&parent.server)?;
+
+    let mut n = 1;
+    let mut name = format!("{}_sub_{n}", parent.name);
+    while name_taken(db, parent.guild_id, &name).await? {
+        n += 1;
+        name = format!("{}_sub_{n}", parent.name);
+    }
+
+    let region = Region {
+        name,
+        display_name: None,
+        parent: Some(parent.name.clone()),
+        perimeter,
+        ..parent.clone()
+    };
+
+    create_plot(server, &region, username)?;
+    db.add_plot(&region).await?;
+
+    Ok(region)
+}
+
+/// Updates the perimeter of an existing plot and keeps the previous
+/// one as revision.
+///
+/// The world only needs to be given to correct the world of plots
+/// created before it was stored.
+pub async fn redefine(
+    db: &Database,
+    rc: &Servers,
+    guild: &conf::GuildSettings,
+    region: Region,
+    world: Option<&str>,
+    perimeter: Perimeter,
+) -> Result<Region> {
+    check_active(&region)?;
+    let server = rc.get(&region.server)?;
+    let owner = region
+        .owner
+        .ok_or_else(|| Rejected("Plots without an owner can not be updated.".into()))?;
+
+    let world = world.map_or_else(|| region.world.clone(), str::to_owned);
+
+    if let Some(parent) = &region.parent {
+        // The parent plot already passed the quota and collision checks.
+        let parent = db
+            .get_plot_by_name(parent)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("The parent plot `{parent}` does not exist."))?;
+        if world != parent.world || !parent.perimeter.contains(&perimeter) {
+            return Err(Rejected(format!(
+                "The subregion must lie entirely inside your plot `{}`!",
+                parent.label()
+            ))
+            .into());
+        }
+    } else {
+        let plots = db.get_user_plots(guild.id, owner).await?;
+        let usage = Usage::of(plots.iter().filter(|p| p.name != region.name));
+        quota::check(&guild.quota, usage, 0, perimeter.size())
+            .map_err(|e| Rejected(e.to_string()))?;
+
+        if needs_approval(&guild.approval, &region.perimeter, &perimeter) {
+            return Err(Rejected(
+                "Your plot can not be enlarged this much without approval. \
+                 Please create a new plot instead."
+                    .into(),
+            )
+            .into());
+        }
+
+        let zones = check_collisions(db, guild, owner, &region.server, &world, &perimeter).await?;
+        if let Some(zone) = entered_zone(&zones, &region) {
+            return Err(Rejected(format!(
+                "Plots in the zone `{}` need to be approved. Please create a new plot instead.",
+                zone.name
+            ))
+            .into());
+        }
+
+        let subregions = db.get_subregions(&region.name).await?;
+        if subregions
+            .iter()
+            .any(|s| s.world != world || !perimeter.contains(&s.perimeter))
+        {
+            return Err(Rejected(
+                "The perimeter of your plot must still contain all of its subregions!".into(),
+            )
+            .into());
+        }
+    }
+
+    let previous = region.clone();
+    let region = Region {
+        perimeter,
+        world,
+        ..region
+    };
+
+    update_plot(server, &region)?;
+    db.add_plot_revision(&previous).await?;
+    db.update_plot(&region).await?;
+
+    Ok(region)
+}
+
+/// Creates the WorldGuard region of a pending plot.
+pub async fn approve(db: &Database, rc: &Servers, region: &Region, username: &str) -> Result<()> {
+    if !region.pending {
+        return Err(Rejected("The plot has already been approved.".into()).into());
+    }
+
+    create_plot(rc.get(&region.server)?, region, username)?;
+    db.approve_plot(&region.name).await?;
+
+    Ok(())
+}
+
+/// Removes the plot from WorldGuard and keeps it in the database
+/// so that it can be restored until it is purged. Pending plots
+/// are deleted right away, as they do not exist in WorldGuard yet.
+pub async fn delete(db: &Database, rc: &Servers, region: &Region) -> Result<()> {
+    if region.pending {
+        return db.delete_plot(&region.name).await;
+    }
+
+    remove_plot(rc.get(&region.server)?, region)?;
+    db.trash_plot(&region.name).await?;
+
+    Ok(())
+}
+
+/// Removes the plot of an inactive owner from WorldGuard but keeps
+/// it in the database so that it can be restored. Pending plots are
+/// deleted instead.
+pub async fn expire(db: &Database, rc: &Servers, region: &Region) -> Result<()> {
+    if region.pending {
+        return db.delete_plot(&region.name).await;
+    }
+
+    remove_plot(rc.get(&region.server)?, region)?;
+    db.expire_plot(&region.name).await?;
+
+    Ok(())
+}
+
+/// Recreates an expired or deleted plot if its area is still free,
+/// including its subregions.
+#[allow(clippy::too_many_arguments)]
+pub async fn restore(
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+    region: &Region,
+    user_id: u64,
+    username: &str,
+) -> Result<()> {
+    let server = rc.get(&region.server)?;
+
+    if let Some(parent) = &region.parent {
+        if db.get_plot_by_name(parent).await?.is_none() {
+            return Err(Rejected(format!("Please restore the plot `{parent}` first.")).into());
+        }
+    } else {
+        let usage = Usage::of(&db.get_user_plots(guild.id, user_id).await?);
+        quota::check(&guild.quota, usage, 1, region.perimeter.size())
+            .map_err(|e| Rejected(e.to_string()))?;
+
+        // The plot has been approved before, so zones requiring
+        // approval are fine.
+        check_collisions(
+            db,
+            guild,
+            user_id,
+            &region.server,
+            &region.world,
+            &region.perimeter,
+        )
+        .await?;
+    }
+
+    recreate(db, server, idcache, &region.name, region, username).await?;
+    db.restore_plot(&region.name).await?;
+
+    // Subregions have been removed along with the plot.
+    for subregion in db.get_subregions(&region.name).await? {
+        recreate(db, server, idcache, &subregion.name, &subregion, username).await?;
+    }
+
+    // Otherwise the plot would expire again right away.
+    if let Some(uuid) = db.get_user_by_id(guild.id, user_id).await? {
+        db.touch_user(&uuid).await?;
+    }
+
+    Ok(())
+}
+
+/// Sets a flag allowed by the config on the plot, or clears it
+/// if no value is given.
+pub async fn set_flag(
+    db: &Database,
+    rc: &Servers,
+    flags: &[conf::Flag],
+    region: &Region,
+    name: &str,
+    value: Option<&str>,
+) -> Result<()> {
+    let flag = flags::find(flags, name).map_err(|e| Rejected(e.to_string()))?;
+    let value = value
+        .map(|v| flags::validate(flag, v))
+        .transpose()
+        .map_err(|e| Rejected(e.to_string()))?;
+    check_active(region)?;
+
+    {
+        let mut conn = rc
+            .get(&region.server)?
+            .get_conn()
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+        let cmd = format!("rg flag -w {} {} {}", region.world, region.name, flag.name);
+        match &value {
+            Some(value) => check_err(conn.cmd(&format!("{cmd} {value}")))?,
+            None => check_err(conn.cmd(&cmd))?,
+        };
+    }
+
+    match &value {
+        Some(value) => db.set_plot_flag(&region.name, &flag.name, value).await?,
+        None => db.clear_plot_flag(&region.name, &flag.name).await?,
+    }
+
+    Ok(())
+}
+
+/// Whether the name can not be used as region ID of a new plot in
+/// the guild, as it is either a region ID or a display name there.
+async fn name_taken(db: &Database, guild_id: u64, name: &str) -> Result<bool> {
+    Ok(db.plot_id_taken(name).await? || db.plot_name_taken(guild_id, name).await?)
+}
+
+/// Sets the name shown instead of the region ID, or removes it if
+/// no name is given.
+pub async fn set_display_name(db: &Database, region: &Region, name: Option<&str>) -> Result<()> {
+    let name = name.map(str::to_lowercase).filter(|n| n != &region.name);
+
+    if let Some(name) = &name {
+        validate_name(name)?;
+        if region.display_name.as_ref() != Some(name)
+            && db.plot_name_taken(region.guild_id, name).await?
+        {
+            return Err(Rejected(format!("The name `{name}` is already taken.")).into());
+        }
+    }
+
+    db.set_plot_display_name(&region.name, name.as_deref())
+        .await?;
+
+    Ok(())
+}
+
+/// Changes the region ID of the plot. As WorldGuard can not rename
+/// regions, the region is created again with the same perimeter,
+/// members and flags before the old one is removed.
+pub async fn rename(
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    region: &Region,
+    name: &str,
+    username: &str,
+) -> Result<Region> {
+    check_active(region)?;
+
+    let name = name.to_lowercase();
+    validate_name(&name)?;
+    // A display name taken by the plot itself may become its ID.
+    if region.display_name.as_ref() != Some(&name) && name_taken(db, region.guild_id, &name).await?
+    {
+        return Err(Rejected(format!("The name `{name}` is already taken.")).into());
+    }
+
+    let server = rc.get(&region.server)?;
+    let renamed = Region {
+        name,
+        display_name: None,
+        ..region.clone()
+    };
+
+    recreate(db, server, idcache, &region.name, &renamed, username).await?;
+
+    // Otherwise the subregions would be removed with the old region.
+    let subregions = db.get_subregions(&region.name).await?;
+    if !subregions.is_empty() {
+        let mut conn = server
+            .get_conn()
+            .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+        for subregion in &subregions {
+            check_err(conn.cmd(&format!(
+                "rg setparent -w {} {} {}",
+                subregion.world, subregion.name, renamed.name
+            )))?;
+        }
+    }
+
+    remove_plot(server, region)?;
+    db.rename_plot(&region.name, &renamed.name).await?;
+
+    Ok(renamed)
+}
+
+/// Checks the name against the characters WorldGuard allows in
+/// region IDs.
+fn validate_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_,'-+/".contains(c));
+
+    if !valid {
+        return Err(Rejected(
+            "Plot names may only contain letters, digits and `_,'-+/`, \
+             and must not be longer than 64 characters."
+                .into(),
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Rejects changes to plots which do not exist in WorldGuard yet.
+pub fn check_active(region: &Region) -> Result<()> {
+    if region.pending {
+        return Err(Rejected(format!(
+            "The plot `{}` is still waiting for approval.",
+            region.name
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Creates the WorldGuard region of a plot including the members
+/// and flags stored for the plot `stored_as`.
+async fn recreate(
+    db: &Database,
+    rc: &Rcon,
+    idcache: &IdCache,
+    stored_as: &str,
+    region: &Region,
+    username: &str,
+) -> Result<()> {
+    let mut members = Vec::new();
+    for uuid in db.get_plot_members(stored_as).await? {
+        members.push(idcache.get_username_by_uuid(&uuid).await?);
+    }
+    let flags = db.get_plot_flags(stored_as).await?;
+
+    create_plot(rc, region, username)?;
+
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    for member in members {
+        check_err(conn.cmd(&format!(
+            "rg addmember -w {} {} {member}",
+            region.world, region.name
+        )))?;
+    }
+    for (flag, value) in flags {
+        check_err(conn.cmd(&format!(
+            "rg flag -w {} {} {flag} {value}",
+            region.world, region.name
+        )))?;
+    }
+
+    Ok(())
+}
+
+fn remove_plot(rc: &Rcon, region: &Region) -> Result<()> {
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    // Subregions are removed along with their parent.
+    check_err(conn.cmd(&format!("rg delete -w {} -f {}", region.world, region.name)))?;
+
+    Ok(())
+}
+
+fn create_plot(rc: &Rcon, region: &Region, user_name: &str) -> Result<()> {
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    select_perimeter(&mut conn, &region.perimeter, &region.world)?;
+    check_err(conn.cmd(&format!("region create {} {}", region.name, user_name)))?;
+
+    if let Some(parent) = &region.parent {
+        check_err(conn.cmd(&format!(
+            "rg setparent -w {} {} {parent}",
+            region.world, region.name
+        )))?;
+        check_err(conn.cmd(&format!(
+            "rg setpriority -w {} {} {SUBREGION_PRIORITY}",
+            region.world, region.name
+        )))?;
+    }
+
+    Ok(())
+}
+
+fn update_plot(rc: &Rcon, region: &Region) -> Result<()> {
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    select_perimeter(&mut conn, &region.perimeter, &region.world)?;
+    check_err(conn.cmd(&format!("rg update {}", region.name)))?;
+
+    Ok(())
+}
+
+fn select_perimeter(conn: &mut Conn, perimeter: &Perimeter, world: &str) -> Result<()> {
+    // TODO: Make configurable or whatever.
+    check_err(conn.cmd(&format!("//world {world}")))?;
+    check_err(conn.cmd(&format!("//pos1 {},0,{}", perimeter.0 .0, perimeter.0 .1)))?;
+    check_err(conn.cmd(&format!("//pos2 {},0,{}", perimeter.1 .0, perimeter.1 .1)))?;
+    check_err(conn.cmd("//expand vert"))?;
+    Ok(())
+}
+
+/// Plots and zones overlapping a perimeter.
+struct Collisions {
+    plots: Vec<Region>,
+    zones: Vec<Zone>,
+}
+
+/// Rejects perimeters overlapping other plots or reserved zones.
+/// Returns the overlapped zones in which plots need approval.
+/// Whether redefining a plot to the new perimeter needs approval.
+/// Plots may always shrink or keep their size, as at least this area
+/// has already been approved or did not need approval.
+fn needs_approval(approval: &conf::Approval, old: &Perimeter, new: &Perimeter) -> bool {
+    approval.required(new) && new.size() > old.size()
+}
+
+/// Returns the first zone needing approval which the plot does not
+/// overlap yet. Zones it already overlaps have been approved along
+/// with the plot, so it may be changed within them.
+fn entered_zone<'a>(zones: &'a [Zone], region: &Region) -> Option<&'a Zone> {
+    zones
+        .iter()
+        .find(|z| z.world != region.world || !z.intersects(&region.perimeter))
+}
+
+async fn check_collisions(
+    db: &Database,
+    guild: &conf::GuildSettings,
+    user_id: u64,
+    server: &str,
+    world: &str,
+    perimeter: &Perimeter,
+) -> Result<Vec<Zone>> {
+    let collisions = find_collisions(db, user_id, server, world, perimeter).await?;
+
+    // Without approval, nobody could approve plots in these zones.
+    let (approval, reserved): (Vec<_>, Vec<_>) = collisions
+        .zones
+        .into_iter()
+        .partition(|z| z.approval && guild.approval.enabled);
+
+    if let Some(zone) = reserved.first() {
+        return Err(Rejected(format!(
+            "The perimeter of your defined plot would overlap the protected zone `{}`!",
+            zone.name
+        ))
+        .into());
+    }
+
+    let plots = collisions.plots;
+    if !plots.is_empty() {
+        return Err(Rejected(format!(
+            "The perimeter of your defined plot would collide with {} other plot{}!",
+            plots.len(),
+            if plots.len() > 1 { "s" } else { "" }
+        ))
+        .into());
+    }
+
+    Ok(approval)
+}
+
+async fn find_collisions(
+    db: &Database,
+    user_id: u64,
+    server: &str,
+    world: &str,
+    perimeter: &Perimeter,
+) -> Result<Collisions> {
+    let plots = db
+        .get_plots()
+        .await?
+        .into_iter()
+        .filter(|p| {
+            p.server == server
+                && p.world == world
+                && p.owner != Some(user_id)
+                && p.parent.is_none()
+                && p.perimeter.intersects(perimeter)
+        })
+        .collect();
+
+    let zones = db
+        .get_zones()
+        .await?
+        .into_iter()
+        .filter(|z| z.server == server && z.world == world && z.intersects(perimeter))
+        .collect();
+
+    Ok(Collisions { plots, zones })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Point;
+
+    #[test]
+    fn redefine_approval() {
+        let approval = conf::Approval {
+            enabled: true,
+            max_area: Some(100),
+            ..Default::default()
+        };
+        let small = Perimeter(Point(0, 0), Point(10, 10));
+        let large = Perimeter(Point(0, 0), Point(20, 20));
+        let larger = Perimeter(Point(0, 0), Point(30, 30));
+
+        assert!(!needs_approval(&approval, &small, &small));
+        assert!(needs_approval(&approval, &small, &large));
+        assert!(needs_approval(&approval, &large, &larger));
+        assert!(!needs_approval(&approval, &larger, &large));
+        assert!(!needs_approval(&approval, &large, &small));
+
+        let disabled = conf::Approval::default();
+        assert!(!needs_approval(&disabled, &small, &larger));
+    }
+
+    #[test]
+    fn redefine_zones() {
+        let zone = |name: &str, a: Point, b: Point| Zone {
+            name: name.into(),
+            server: "default".into(),
+            world: "world".into(),
+            points: vec![a, b],
+            approval: true,
+        };
+        let region = Region {
+            name: "steve_plot_1".into(),
+            guild_id: 1,
+            owner: Some(1),
+            server: "default".into(),
+            world: "world".into(),
+            perimeter: Perimeter(Point(0, 0), Point(10, 10)),
+            pending: false,
+            display_name: None,
+            parent: None,
+        };
+        let inside = zone("inside", Point(-50, -50), Point(50, 50));
+        let next = zone("next", Point(20, 0), Point(30, 10));
+
+        let zones = [inside, next];
+
+        assert!(entered_zone(&zones[..1], &region).is_none());
+        assert_eq!(
+            entered_zone(&zones, &region).map(|z| z.name.as_str()),
+            Some("next")
+        );
+    }
+
+    #[test]
+    fn names() {
+        assert!(validate_name("steve_plot_1").is_ok());
+        assert!(validate_name("castle-2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("my castle").is_err());
+        assert!(validate_name("schloss-ä").is_err());
+        assert!(validate_name(&"a".repeat(65)).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use crate::conf::{self, AccountMode};
+use anyhow::Result;
+use minecraft_uuid::error::APIError;
+use minecraft_uuid::{
+    floodgate_xuid, is_offline_uuid, AshconProvider, FallbackProvider, FloodgateProvider,
+    MinecraftUuid, MojangProvider, OfflineProvider, PlayerDbProvider, ProfileProvider,
+};
+use serenity::async_trait;
+
+/// Resolves Minecraft accounts according to the
+/// account modes accepted in the config.
+///
+/// Usernames carrying the Floodgate prefix are resolved as
+/// Bedrock players. All other usernames are looked up using
+/// the configured provider chain. If offline mode is accepted
+/// as well, offline UUIDs are used for names which are unknown
+/// to Mojang, or for all names if online mode is not accepted.
+pub struct AccountProvider {
+    modes: Vec<AccountMode>,
+    online: FallbackProvider,
+    floodgate: FloodgateProvider,
+}
+
+impl AccountProvider {
+    pub fn from_config(cfg: &conf::Profiles) -> Self {
+        let online = cfg
+            .providers
+            .iter()
+            .fold(FallbackProvider::default(), |chain, p| match p {
+                conf::ProfileProvider::Mojang => chain.with(MojangProvider),
+                conf::ProfileProvider::PlayerDb => chain.with(PlayerDbProvider),
+                conf::ProfileProvider::Ashcon => chain.with(AshconProvider),
+                conf::ProfileProvider::Offline => chain.with(OfflineProvider),
+            });
+
+        Self {
+            modes: cfg.modes.clone(),
+            online,
+            floodgate: FloodgateProvider::new(&cfg.floodgate.prefix),
+        }
+    }
+
+    fn accepts(&self, mode: AccountMode) -> bool {
+        self.modes.contains(&mode)
+    }
+
+    /// Returns the Xbox gamertag of the given username if it
+    /// belongs to a Bedrock player.
+    pub fn bedrock_gamertag<'a>(&self, username: &'a str) -> Option<&'a str> {
+        if !self.accepts(AccountMode::Floodgate) {
+            return None;
+        }
+        self.floodgate.strip_prefix(username)
+    }
+
+    /// Builds the RCON command to add or remove the given
+    /// player to or from the whitelist.
+    ///
+    /// Bedrock players are managed using the `fwhitelist`
+    /// command provided by Floodgate.
+    pub fn whitelist_command(&self, action: &str, username: &str) -> String {
+        match self.bedrock_gamertag(username) {
+            Some(gamertag) => format!("fwhitelist {action} {gamertag}"),
+            None => format!("whitelist {action} {username}"),
+        }
+    }
+}
+
+fn is_not_found(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<APIError>()
+        .is_some_and(|err| err.status_code() == 404)
+}
+
+#[async_trait]
+impl ProfileProvider for AccountProvider {
+    fn name(&self) -> &str {
+        "accounts"
+    }
+
+    async fn get_uuid_by_username(&self, username: &str) -> Result<MinecraftUuid> {
+        if self.bedrock_gamertag(username).is_some() {
+            return self.floodgate.get_uuid_by_username(username).await;
+        }
+
+        if self.accepts(AccountMode::Online) {
+            return match self.online.get_uuid_by_username(username).await {
+                Err(err) if is_not_found(&err) && self.accepts(AccountMode::Offline) => {
+                    OfflineProvider.get_uuid_by_username(username).await
+                }
+                res => res,
+            };
+        }
+
+        if self.accepts(AccountMode::Offline) {
+            return OfflineProvider.get_uuid_by_username(username).await;
+        }
+
+        anyhow::bail!("This kind of Minecraft account is not accepted on this server.")
+    }
+
+    async fn get_username_by_uuid(&self, uuid: &MinecraftUuid) -> Result<String> {
+        if floodgate_xuid(uuid).is_some() {
+            return self.floodgate.get_username_by_uuid(uuid).await;
+        }
+
+        if is_offline_uuid(uuid) {
+            return OfflineProvider.get_username_by_uuid(uuid).await;
+        }
+
+        self.online.get_username_by_uuid(uuid).await
+    }
+}
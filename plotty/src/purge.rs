@@ -0,0 +1,39 @@
+use crate::conf;
+use crate::db::Database;
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use log::{error, info};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time;
+
+const INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Periodically deletes plots for good which have been deleted
+/// or expired for longer than they can be restored.
+pub async fn run(cfg: Arc<conf::Shared>, db: Arc<Database>, shutdown: Arc<Shutdown>) {
+    let mut interval = time::interval(INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        let Some(_guard) = shutdown.start() else {
+            return;
+        };
+
+        if let Err(err) = purge(&cfg.get(), &db).await {
+            error!("Purging plots failed: {err}");
+        }
+    }
+}
+
+async fn purge(cfg: &conf::Config, db: &Database) -> Result<()> {
+    let deleted = db.purge_deleted_plots(cfg.trash.retention_days).await?;
+    let expired = db.purge_expired_plots(cfg.expiry.grace_days).await?;
+
+    if deleted + expired > 0 {
+        info!("Purged {deleted} deleted and {expired} expired plot(s)");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use crate::conf;
+use crate::models::Region;
+use anyhow::Result;
+use std::fmt;
+
+/// The amount of plots and total area claimed by a user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Usage {
+    pub plots: usize,
+    pub area: i64,
+}
+
+impl Usage {
+    /// Subregions are not counted, as their area is already covered
+    /// by their parent plot.
+    pub fn of<'a>(plots: impl IntoIterator<Item = &'a Region>) -> Self {
+        plots
+            .into_iter()
+            .filter(|p| p.parent.is_none())
+            .fold(Self::default(), |u, p| Self {
+                plots: u.plots + 1,
+                area: u.area + p.perimeter.size(),
+            })
+    }
+}
+
+/// Checks whether adding `plots` plots with a total area of `area`
+/// to the given usage would exceed the quota.
+///
+/// The plot limit is only checked when plots are added, so that
+/// plots can still be redefined after the limit has been lowered.
+pub fn check(quota: &conf::Quota, usage: Usage, plots: usize, area: i64) -> Result<()> {
+    if let Some(max) = quota.max_plots {
+        if plots > 0 && usage.plots + plots > max {
+            anyhow::bail!("You have reached the maximum of {max} plots.");
+        }
+    }
+
+    if let Some(max) = quota.max_area {
+        if usage.area + area > max {
+            anyhow::bail!(
+                "Your plots would cover {}m² in total, but only {max}m² are allowed.",
+                usage.area + area
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Displays a used amount against an optional limit, like `2 / 5`.
+pub struct Limit<T>(pub T, pub Option<T>);
+
+impl<T: fmt::Display> fmt::Display for Limit<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.1 {
+            Some(max) => write!(f, "{} / {max}", self.0),
+            None => write!(f, "{} / unlimited", self.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Perimeter, Point};
+
+    fn plot(size: i64) -> Region {
+        Region {
+            perimeter: Perimeter(Point(0, 0), Point(size, 1)),
+            name: String::new(),
+            guild_id: 1,
+            owner: Some(1),
+            server: "default".into(),
+            world: "world".into(),
+            pending: false,
+            display_name: None,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn usage_of() {
+        let usage = Usage::of(&[plot(10), plot(20)]);
+        assert_eq!(usage, Usage { plots: 2, area: 30 });
+        assert_eq!(Usage::of(&[]), Usage::default());
+
+        let sub = Region {
+            parent: Some("parent".into()),
+            ..plot(5)
+        };
+        assert_eq!(Usage::of(&[plot(10), sub]), Usage { plots: 1, area: 10 });
+    }
+
+    #[test]
+    fn check_quota() {
+        let quota = conf::Quota {
+            max_plots: Some(2),
+            max_area: Some(100),
+        };
+        let usage = Usage { plots: 1, area: 50 };
+
+        assert!(check(&quota, usage, 1, 50).is_ok());
+        assert!(check(&quota, usage, 1, 51).is_err());
+        assert!(check(&quota, usage, 2, 10).is_err());
+
+        let usage = Usage { plots: 3, area: 50 };
+        assert!(check(&quota, usage, 0, 10).is_ok());
+        assert!(check(&conf::Quota::default(), usage, 10, 1000).is_ok());
+    }
+}
@@ -0,0 +1,245 @@
+//! Staff review of plots which need approval before they are
+//! created in WorldGuard.
+//!
+//! The buttons of a review request carry the plot name, so that
+//! they keep working while the request is open for hours or across
+//! restarts of plotty.
+
+use crate::conf;
+use crate::db::Database;
+use crate::idcache::IdCache;
+use crate::mc::Servers;
+use crate::models::Region;
+use crate::plots;
+use anyhow::Result;
+use log::{info, warn};
+use serenity::builder::CreateEmbed;
+use serenity::http::Http;
+use serenity::model::prelude::component::ButtonStyle;
+use serenity::model::prelude::interaction::message_component::MessageComponentInteraction;
+use serenity::model::prelude::interaction::InteractionResponseType;
+use serenity::model::prelude::{ChannelId, UserId};
+use serenity::prelude::Context;
+use serenity::utils::Color;
+
+const APPROVE_PREFIX: &str = "review-approve:";
+const DENY_PREFIX: &str = "review-deny:";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    Approve,
+    Deny,
+}
+
+/// Returns the decision and plot name of a review button.
+fn parse_custom_id(id: &str) -> Option<(Decision, &str)> {
+    if let Some(name) = id.strip_prefix(APPROVE_PREFIX) {
+        return Some((Decision::Approve, name));
+    }
+    id.strip_prefix(DENY_PREFIX)
+        .map(|name| (Decision::Deny, name))
+}
+
+/// Whether the component belongs to a review request.
+pub fn is_review(component: &MessageComponentInteraction) -> bool {
+    parse_custom_id(&component.data.custom_id).is_some()
+}
+
+/// Posts the pending plot to the review channel of the guild.
+///
+/// The plot is removed again if the request can not be posted, as
+/// nobody would be able to approve it.
+pub async fn request(
+    http: &Http,
+    db: &Database,
+    guild: &conf::GuildSettings,
+    region: &Region,
+) -> Result<()> {
+    let res = ChannelId(guild.approval.channel)
+        .send_message(http, |m| {
+            m.set_embed(embed(region).color(Color::ORANGE).to_owned())
+                .components(|c| {
+                    c.create_action_row(|row| {
+                        row.create_button(|btn| {
+                            btn.custom_id(format!("{APPROVE_PREFIX}{}", region.name))
+                                .style(ButtonStyle::Success)
+                                .label("Approve")
+                        })
+                        .create_button(|btn| {
+                            btn.custom_id(format!("{DENY_PREFIX}{}", region.name))
+                                .style(ButtonStyle::Danger)
+                                .label("Deny")
+                        })
+                    })
+                })
+        })
+        .await;
+
+    if let Err(err) = res {
+        db.delete_plot(&region.name).await?;
+        anyhow::bail!("Requesting the approval of the plot failed: {err}");
+    }
+
+    Ok(())
+}
+
+/// Handles a click on the approve or deny button of a review
+/// request.
+pub async fn handle(
+    ctx: &Context,
+    component: &MessageComponentInteraction,
+    db: &Database,
+    rc: &Servers,
+    idcache: &IdCache,
+    guild: &conf::GuildSettings,
+) -> Result<()> {
+    let Some((decision, plot_name)) = parse_custom_id(&component.data.custom_id) else {
+        return Ok(());
+    };
+
+    if !crate::commands::admin::is_admin(component.member.as_ref(), guild) {
+        component
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::ChannelMessageWithSource)
+                    .interaction_response_data(|d| {
+                        d.ephemeral(true).add_embed(
+                            CreateEmbed::default()
+                                .color(Color::RED)
+                                .description("You are not allowed to review plots.")
+                                .to_owned(),
+                        )
+                    })
+            })
+            .await?;
+        return Ok(());
+    }
+
+    // Creating the region may take longer than Discord waits
+    // for a response.
+    component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::DeferredUpdateMessage)
+        })
+        .await?;
+
+    let region = db
+        .get_plot_by_name(plot_name)
+        .await?
+        .filter(|r| r.guild_id == guild.id && r.pending);
+
+    let Some(region) = region else {
+        component
+            .edit_original_interaction_response(&ctx.http, |r| {
+                r.set_embed(
+                    CreateEmbed::default()
+                        .description(format!(
+                            "The plot `{plot_name}` is no longer waiting for approval."
+                        ))
+                        .to_owned(),
+                )
+                .components(|c| c)
+            })
+            .await?;
+        return Ok(());
+    };
+
+    let reviewer = component.user.id;
+    let (color, outcome, message) = match decision {
+        Decision::Approve => {
+            let owner = region
+                .owner
+                .ok_or_else(|| anyhow::anyhow!("The plot no longer has an owner."))?;
+            let uuid = db
+                .get_user_by_id(guild.id, owner)
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("The owner is no longer bound."))?;
+            let username = idcache.get_username_by_uuid(&uuid).await?.to_lowercase();
+
+            plots::approve(db, rc, &region, &username).await?;
+            (
+                Color::FOOYOO,
+                format!("Approved by <@{reviewer}>"),
+                format!("Your plot `{}` has been approved! 🎉", region.name),
+            )
+        }
+        Decision::Deny => {
+            plots::delete(db, rc, &region).await?;
+            (
+                Color::RED,
+                format!("Denied by <@{reviewer}>"),
+                format!(
+                    "Your request for the plot `{}` has been denied.",
+                    region.name
+                ),
+            )
+        }
+    };
+
+    info!("{} by {}", outcome, component.user.tag());
+
+    component
+        .edit_original_interaction_response(&ctx.http, |r| {
+            r.set_embed(
+                embed(&region)
+                    .color(color)
+                    .field("Decision", &outcome, false)
+                    .to_owned(),
+            )
+            .components(|c| c)
+        })
+        .await?;
+
+    if let Some(owner) = region.owner {
+        if let Err(err) = notify(&ctx.http, owner, &message).await {
+            warn!("Notifying <@{owner}> about the review failed: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+fn embed(region: &Region) -> CreateEmbed {
+    let p = &region.perimeter;
+    CreateEmbed::default()
+        .title(format!("Plot request {}", region.name))
+        .field(
+            "Requested by",
+            region
+                .owner
+                .map_or("*none*".into(), |id| format!("<@{id}>")),
+            true,
+        )
+        .field("Server", &region.server, true)
+        .field("World", &region.world, true)
+        .field(
+            "Perimeter",
+            format!("`{}, {}` → `{}, {}`", p.0 .0, p.0 .1, p.1 .0, p.1 .1),
+            true,
+        )
+        .field("Area", format!("{}m²", p.size()), true)
+        .to_owned()
+}
+
+async fn notify(http: &Http, discord_id: u64, message: &str) -> Result<()> {
+    let channel = UserId(discord_id).create_dm_channel(http).await?;
+    channel.say(http, message).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_ids() {
+        assert_eq!(
+            parse_custom_id("review-approve:steve_plot_1"),
+            Some((Decision::Approve, "steve_plot_1"))
+        );
+        assert_eq!(
+            parse_custom_id("review-deny:steve_plot_1"),
+            Some((Decision::Deny, "steve_plot_1"))
+        );
+        assert_eq!(parse_custom_id("cnpdu0b9fkvsq1mbuagg"), None);
+    }
+}
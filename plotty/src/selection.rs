@@ -0,0 +1,94 @@
+//! Reading the location of online players over RCON, so that plots
+//! can be created without typing coordinates.
+//!
+//! WorldEdit selections can not be read this way, as commands run
+//! over RCON do not have access to the session of the player.
+
+use crate::mc::{check_err, Conn, Rcon};
+use crate::models::{Perimeter, Point};
+use crate::plots::Rejected;
+use anyhow::Result;
+
+/// Returns the world the player is in and a square with the given
+/// radius centred on their position.
+pub fn read_square(rc: &Rcon, player: &str, radius: i64) -> Result<(String, Perimeter)> {
+    let mut conn = rc
+        .get_conn()
+        .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+
+    let world = read_world(&mut conn, player)?;
+    let res = check_err(conn.cmd(&format!("data get entity {player} Pos")))?;
+
+    let Point(x, z) = parse_pos(&res.body).ok_or_else(not_online)?;
+    let perimeter = Perimeter(Point(x - radius, z - radius), Point(x + radius, z + radius));
+
+    Ok((world, perimeter))
+}
+
+fn read_world(conn: &mut Conn, player: &str) -> Result<String> {
+    let res = check_err(conn.cmd(&format!("data get entity {player} Dimension")))?;
+
+    parse_dimension(&res.body).ok_or_else(not_online)
+}
+
+fn not_online() -> anyhow::Error {
+    Rejected("You need to be online on the server for this.".into()).into()
+}
+
+/// Returns the block position from the response to `data get entity
+/// <name> Pos`, like `Steve has the following entity data: [-12.3d,
+/// 64.0d, 100.5d]`.
+fn parse_pos(body: &str) -> Option<Point> {
+    let (_, data) = body.split_once(": [")?;
+    let coords = data
+        .trim_end()
+        .strip_suffix(']')?
+        .split(',')
+        .map(|c| c.trim().trim_end_matches('d').parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .ok()?;
+
+    match coords.as_slice() {
+        [x, _, z] => Some(Point(x.floor() as i64, z.floor() as i64)),
+        _ => None,
+    }
+}
+
+/// Returns the world name used for plots from the response to `data
+/// get entity <name> Dimension`, like `Steve has the following entity
+/// data: "minecraft:overworld"`.
+fn parse_dimension(body: &str) -> Option<String> {
+    let (_, data) = body.split_once(": ")?;
+    let dimension = data.trim().trim_matches('"');
+
+    let world = match dimension {
+        "minecraft:overworld" => "world",
+        "minecraft:the_nether" => "nether",
+        "minecraft:the_end" => "the_end",
+        _ => dimension.split_once(':')?.1,
+    };
+    Some(world.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pos() {
+        assert_eq!(
+            parse_pos("Steve has the following entity data: [-12.3d, 64.0d, 100.5d]"),
+            Some(Point(-13, 100))
+        );
+        assert_eq!(parse_pos("No entity was found"), None);
+    }
+
+    #[test]
+    fn dimension() {
+        let res = |d: &str| parse_dimension(&format!("Steve has the following entity data: {d}"));
+        assert_eq!(res("\"minecraft:overworld\""), Some("world".into()));
+        assert_eq!(res("\"minecraft:the_nether\""), Some("nether".into()));
+        assert_eq!(res("\"custom:mining\""), Some("mining".into()));
+        assert_eq!(parse_dimension("No entity was found"), None);
+    }
+}
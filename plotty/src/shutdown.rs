@@ -0,0 +1,151 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Keeps track of running tasks so that shutting down can wait
+/// for them to finish.
+#[derive(Default)]
+pub struct Shutdown {
+    closing: AtomicBool,
+    running: AtomicUsize,
+    idle: Notify,
+    closed: Notify,
+}
+
+impl Shutdown {
+    /// Registers a task which runs until the returned guard is
+    /// dropped. Returns `None` once shutting down has begun.
+    pub fn start(&self) -> Option<Guard<'_>> {
+        self.running.fetch_add(1, Ordering::SeqCst);
+        let guard = Guard(self);
+
+        if self.closing.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some(guard)
+    }
+
+    /// Stops accepting new tasks and waits until all running tasks
+    /// have finished. Returns `false` if the timeout elapsed first.
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.closing.store(true, Ordering::SeqCst);
+        self.closed.notify_waiters();
+
+        let wait = async {
+            loop {
+                let idle = self.idle.notified();
+                if self.running.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                idle.await;
+            }
+        };
+
+        tokio::time::timeout(timeout, wait).await.is_ok()
+    }
+
+    /// Resolves once shutting down has begun.
+    pub async fn closing(&self) {
+        loop {
+            let closed = self.closed.notified();
+            if self.closing.load(Ordering::SeqCst) {
+                return;
+            }
+            closed.await;
+        }
+    }
+
+    /// Waits for user input, but gives up once shutting down has
+    /// begun, as the input could take longer than the drain timeout.
+    pub async fn interruptible<T>(&self, f: impl Future<Output = T>) -> anyhow::Result<T> {
+        tokio::select! {
+            res = f => Ok(res),
+            _ = self.closing() => anyhow::bail!("plotty is restarting. Please try again shortly."),
+        }
+    }
+
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+}
+
+pub struct Guard<'a>(&'a Shutdown);
+
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        if self.0.running.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.idle.notify_waiters();
+        }
+    }
+}
+
+/// Resolves once SIGINT or SIGTERM has been received.
+pub async fn signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut s) => {
+                s.recv().await;
+            }
+            Err(err) => {
+                log::error!("Listening for SIGTERM failed: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        res = tokio::signal::ctrl_c() => {
+            if let Err(err) = res {
+                log::error!("Listening for SIGINT failed: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+        _ = terminate => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn drain_waits_for_running_tasks() {
+        let shutdown = Shutdown::default();
+        assert!(shutdown.drain(Duration::ZERO).await);
+
+        let shutdown = Shutdown::default();
+        let guard = shutdown.start().unwrap();
+        assert!(!shutdown.drain(Duration::from_millis(10)).await);
+        assert!(shutdown.start().is_none());
+        assert_eq!(shutdown.running(), 1);
+
+        let (drained, _) = tokio::join!(shutdown.drain(Duration::from_secs(5)), async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(guard);
+        });
+        assert!(drained);
+        assert_eq!(shutdown.running(), 0);
+    }
+
+    #[tokio::test]
+    async fn drain_interrupts_waits() {
+        let shutdown = Shutdown::default();
+        assert_eq!(shutdown.interruptible(async { 1 }).await.unwrap(), 1);
+
+        let _guard = shutdown.start().unwrap();
+        let (res, drained) = tokio::join!(
+            shutdown.interruptible(std::future::pending::<()>()),
+            async {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                shutdown.drain(Duration::from_millis(10)).await
+            }
+        );
+        assert!(res.is_err());
+        assert!(!drained);
+    }
+}
@@ -0,0 +1,197 @@
+use crate::conf;
+use crate::mc::{check_err, Rcon};
+use anyhow::Result;
+use rand::Rng;
+use regex::Regex;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::time::{self, Instant};
+
+/// Characters used for verification codes. Characters which
+/// are easily mixed up in the Minecraft font are left out.
+const CODE_CHARS: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+
+pub fn generate_code(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..len)
+        .map(|_| CODE_CHARS[rng.gen_range(0..CODE_CHARS.len())] as char)
+        .collect()
+}
+
+/// Waits until the given player has entered the given code in game.
+///
+/// Returns `false` if the code has not been entered before the
+/// configured timeout has passed.
+pub async fn verify(cfg: &conf::Verification, rc: &Rcon, player: &str, code: &str) -> Result<bool> {
+    let mut source = Source::open(&cfg.source, player, code)?;
+
+    let deadline = Instant::now() + Duration::from_secs(cfg.timeout_secs);
+    let period = Duration::from_secs(cfg.interval_secs.max(1));
+
+    while Instant::now() < deadline {
+        time::sleep(period).await;
+        if source.poll(rc)? {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Compiles the configured pattern into a regular expression which
+/// matches the given player entering the given code.
+///
+/// The player name is matched case-insensitively, as Minecraft names
+/// are, while the code has to match exactly.
+pub fn render(pattern: &str, player: &str, code: &str) -> Result<Regex> {
+    let pattern = pattern
+        .replace("{player}", &format!("(?i:{})", regex::escape(player)))
+        .replace("{code}", &regex::escape(code));
+
+    Regex::new(&pattern).map_err(|e| anyhow::anyhow!("Invalid verification pattern: {e}"))
+}
+
+enum Source {
+    Rcon { command: String, pattern: Regex },
+    Log { tail: LogTail, pattern: Regex },
+}
+
+impl Source {
+    fn open(cfg: &conf::VerificationSource, player: &str, code: &str) -> Result<Self> {
+        Ok(match cfg {
+            conf::VerificationSource::Rcon { command, pattern } => Self::Rcon {
+                command: command.replace("{player}", player).replace("{code}", code),
+                pattern: render(pattern, player, code)?,
+            },
+            conf::VerificationSource::Log { path, pattern } => Self::Log {
+                tail: LogTail::open(path)?,
+                pattern: render(pattern, player, code)?,
+            },
+        })
+    }
+
+    fn poll(&mut self, rc: &Rcon) -> Result<bool> {
+        match self {
+            Self::Rcon { command, pattern } => {
+                let mut conn = rc
+                    .get_conn()
+                    .map_err(|e| anyhow::anyhow!("RCON connection failed: {e}"))?;
+                let msg = check_err(conn.cmd(command))?;
+                Ok(msg.body.lines().any(|l| pattern.is_match(l)))
+            }
+            Self::Log { tail, pattern } => {
+                Ok(tail.read_lines()?.iter().any(|l| pattern.is_match(l)))
+            }
+        }
+    }
+}
+
+/// Reads lines appended to a log file since it has been opened.
+struct LogTail {
+    path: PathBuf,
+    offset: u64,
+}
+
+impl LogTail {
+    fn open(path: &str) -> Result<Self> {
+        let offset = File::open(path)
+            .and_then(|f| f.metadata())
+            .map_err(|e| anyhow::anyhow!("Opening log file {path} failed: {e}"))?
+            .len();
+
+        Ok(Self {
+            path: path.into(),
+            offset,
+        })
+    }
+
+    fn read_lines(&mut self) -> Result<Vec<String>> {
+        let mut f = File::open(&self.path)?;
+
+        // The log has been rotated.
+        if f.metadata()?.len() < self.offset {
+            self.offset = 0;
+        }
+
+        f.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        f.read_to_end(&mut buf)?;
+
+        // Incomplete lines are read again on the next call.
+        let Some(end) = buf.iter().rposition(|&b| b == b'\n') else {
+            return Ok(vec![]);
+        };
+        self.offset += end as u64 + 1;
+
+        Ok(String::from_utf8_lossy(&buf[..end])
+            .lines()
+            .map(str::to_owned)
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+
+    #[test]
+    fn code_generation() {
+        let code = generate_code(6);
+        assert_eq!(code.len(), 6);
+        assert!(code.bytes().all(|b| CODE_CHARS.contains(&b)));
+    }
+
+    #[test]
+    fn pattern_rendering() {
+        let pattern = match conf::VerificationSource::default() {
+            conf::VerificationSource::Log { pattern, .. } => pattern,
+            _ => unreachable!(),
+        };
+        let re = render(&pattern, "zekroTJA", "AB12").unwrap();
+
+        assert!(re.is_match("[12:00:01] [Server thread/INFO]: <zekroTJA> AB12"));
+        assert!(re.is_match("[12:00:01] [Async Chat Thread - #0/INFO]: <ZekroTJA> AB12"));
+        assert!(re.is_match("[12:00:01] [Server thread/INFO]: [Not Secure] <zekroTJA> AB12"));
+        // The code has to match exactly.
+        assert!(!re.is_match("[12:00:01] [Server thread/INFO]: <zekroTJA> ab12"));
+        assert!(!re.is_match("[12:00:01] [Server thread/INFO]: <zekroTJA> AB123"));
+        // Other players can not enter the code on behalf of the player.
+        assert!(!re.is_match("[12:00:01] [Server thread/INFO]: <Mallory> <zekroTJA> AB12"));
+        assert!(!re.is_match("[12:00:01] [Server thread/INFO]: <Mallory> zekroTJA AB12"));
+
+        assert!(render("<{player}> (", "zekroTJA", "AB12").is_err());
+    }
+
+    #[test]
+    fn log_tail() {
+        let path = std::env::temp_dir().join(format!("plotty-{}.log", xid::new()));
+        let append = |s: &str| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .unwrap()
+                .write_all(s.as_bytes())
+                .unwrap()
+        };
+
+        append("[12:00:00] <zekroTJA> old line\n");
+        let mut tail = LogTail::open(path.to_str().unwrap()).unwrap();
+        assert!(tail.read_lines().unwrap().is_empty());
+
+        append("[12:00:01] <zekroTJA> AB12");
+        assert!(tail.read_lines().unwrap().is_empty());
+
+        append("CD\n");
+        assert_eq!(tail.read_lines().unwrap(), ["[12:00:01] <zekroTJA> AB12CD"]);
+
+        std::fs::write(&path, "rotated\n").unwrap();
+        assert_eq!(tail.read_lines().unwrap(), ["rotated"]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,375 @@
+//! Preview of a new plot before it is created, with buttons to move
+//! its edges until the perimeter fits.
+
+use crate::db::Database;
+use crate::mc::Servers;
+use crate::models::{Perimeter, Point, Region, Zone};
+use crate::plots::Rejected;
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use serenity::builder::{CreateComponents, CreateEmbed};
+use serenity::model::prelude::component::ButtonStyle;
+use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
+use serenity::model::prelude::interaction::InteractionResponseType;
+use serenity::prelude::Context;
+use serenity::utils::Color;
+use std::time::Duration;
+
+/// The number of cells per row and column of the mini-map.
+const MAP_CELLS: i64 = 12;
+
+/// How many neighbouring plots are listed.
+const NEIGHBOURS: usize = 3;
+
+const STEPS: [i64; 4] = [-10, -1, 1, 10];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Side {
+    const ALL: [Side; 4] = [Side::North, Side::East, Side::South, Side::West];
+
+    fn key(self) -> char {
+        match self {
+            Side::North => 'n',
+            Side::East => 'e',
+            Side::South => 's',
+            Side::West => 'w',
+        }
+    }
+
+    fn from_key(key: char) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.key() == key)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    Confirm,
+    Cancel,
+    /// Moves an edge outwards by the given number of blocks, or
+    /// inwards if negative.
+    Nudge(Side, i64),
+}
+
+/// Returns the action of a button of the preview with the given
+/// prefix, like `<prefix>:n:-10`.
+fn parse_custom_id(prefix: &str, id: &str) -> Option<Action> {
+    let action = id.strip_prefix(prefix)?.strip_prefix(':')?;
+    match action {
+        "confirm" => Some(Action::Confirm),
+        "cancel" => Some(Action::Cancel),
+        _ => {
+            let (side, delta) = action.split_once(':')?;
+            let mut side = side.chars();
+            let side = Side::from_key(side.next()?).filter(|_| side.next().is_none())?;
+            Some(Action::Nudge(side, delta.parse().ok()?))
+        }
+    }
+}
+
+/// Moves one edge of the perimeter. Edges are not moved past the
+/// opposite edge.
+fn nudge(perimeter: &Perimeter, side: Side, delta: i64) -> Perimeter {
+    let Perimeter(Point(min_x, min_z), Point(max_x, max_z)) = perimeter.normalize();
+    let nudged = match side {
+        Side::North => Perimeter(Point(min_x, min_z - delta), Point(max_x, max_z)),
+        Side::East => Perimeter(Point(min_x, min_z), Point(max_x + delta, max_z)),
+        Side::South => Perimeter(Point(min_x, min_z), Point(max_x, max_z + delta)),
+        Side::West => Perimeter(Point(min_x - delta, min_z), Point(max_x, max_z)),
+    };
+
+    let Perimeter(Point(ax, az), Point(bx, bz)) = nudged;
+    if ax >= bx || az >= bz {
+        return perimeter.normalize();
+    }
+    nudged
+}
+
+/// Renders the perimeter and its surroundings as text, with north
+/// at the top. The plot covers about half of the map.
+fn render_map(perimeter: &Perimeter, others: &[Perimeter], zones: &[Zone]) -> String {
+    let p = perimeter.normalize();
+    let (width, length) = (p.1 .0 - p.0 .0, p.1 .1 - p.0 .1);
+    let scale = (width.max(length) * 2 + MAP_CELLS - 1) / MAP_CELLS;
+    let scale = scale.max(1);
+
+    let x0 = (p.0 .0 + p.1 .0) / 2 - scale * MAP_CELLS / 2;
+    let z0 = (p.0 .1 + p.1 .1) / 2 - scale * MAP_CELLS / 2;
+
+    let covers = |p: &Perimeter, cell: &Perimeter| {
+        p.intersects(cell) || p.contains(cell) || cell.contains(p)
+    };
+
+    let mut map = String::new();
+    for row in 0..MAP_CELLS {
+        for col in 0..MAP_CELLS {
+            let cell = Perimeter(
+                Point(x0 + col * scale, z0 + row * scale),
+                Point(x0 + (col + 1) * scale, z0 + (row + 1) * scale),
+            );
+            map.push_str(if covers(&p, &cell) {
+                "██"
+            } else if others.iter().any(|o| covers(o, &cell)) {
+                "▓▓"
+            } else if zones.iter().any(|z| z.intersects(&cell)) {
+                "░░"
+            } else {
+                "··"
+            });
+        }
+        map.push('\n');
+    }
+    map
+}
+
+/// Shows a preview of the new plot until the user confirms or cancels
+/// it. Returns the perimeter after all adjustments, or `None` if the
+/// user canceled.
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    db: &Database,
+    rc: &Servers,
+    user_id: u64,
+    server: Option<&str>,
+    world: &str,
+    perimeter: Perimeter,
+    shutdown: &Shutdown,
+) -> Result<Option<Perimeter>> {
+    let server = rc
+        .get_or_default(server)
+        .map_err(|e| Rejected(e.to_string()))?
+        .name()
+        .to_owned();
+    let multiple_servers = rc.names().count() > 1;
+
+    // The surroundings are only loaded once, as the preview is short-lived.
+    let plots = db
+        .get_plots()
+        .await?
+        .into_iter()
+        .filter(|p| p.server == server && p.world == world && p.parent.is_none())
+        .collect::<Vec<_>>();
+    let zones = db
+        .get_zones()
+        .await?
+        .into_iter()
+        .filter(|z| z.server == server && z.world == world)
+        .collect::<Vec<_>>();
+
+    let preview = |perimeter: &Perimeter| {
+        let mut embed = embed(perimeter, user_id, world, &plots, &zones);
+        if multiple_servers {
+            embed.field("Server", &server, true);
+        }
+        embed
+    };
+
+    let prefix = xid::new().to_string();
+    let m = command
+        .create_followup_message(&ctx.http, |msg| {
+            msg.add_embed(preview(&perimeter))
+                .components(|c| buttons(c, &prefix))
+        })
+        .await?;
+
+    let mut perimeter = perimeter.normalize();
+    loop {
+        let interaction = shutdown
+            .interruptible(
+                m.await_component_interaction(ctx)
+                    .timeout(Duration::from_secs(300)),
+            )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Timed out."))?;
+
+        let (embed, done) = match parse_custom_id(&prefix, &interaction.data.custom_id) {
+            Some(Action::Confirm) => (
+                CreateEmbed::default()
+                    .description("Creating your plot…")
+                    .to_owned(),
+                Some(Some(perimeter.clone())),
+            ),
+            Some(Action::Cancel) | None => (
+                CreateEmbed::default()
+                    .description("Action canceled.")
+                    .to_owned(),
+                Some(None),
+            ),
+            Some(Action::Nudge(side, delta)) => {
+                perimeter = nudge(&perimeter, side, delta);
+                (preview(&perimeter), None)
+            }
+        };
+
+        interaction
+            .create_interaction_response(&ctx.http, |r| {
+                r.kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|d| {
+                        d.set_embed(embed);
+                        match done {
+                            Some(_) => d.components(|c| c),
+                            None => d.components(|c| buttons(c, &prefix)),
+                        }
+                    })
+            })
+            .await?;
+
+        if let Some(res) = done {
+            return Ok(res);
+        }
+    }
+}
+
+fn embed(
+    perimeter: &Perimeter,
+    user_id: u64,
+    world: &str,
+    plots: &[Region],
+    zones: &[Zone],
+) -> CreateEmbed {
+    let p = perimeter.normalize();
+
+    let mut neighbours = plots.iter().collect::<Vec<_>>();
+    neighbours.sort_by_key(|r| r.perimeter.distance(&p));
+    let collides = neighbours
+        .iter()
+        .any(|r| r.owner != Some(user_id) && r.perimeter.intersects(&p));
+    let neighbours = neighbours
+        .iter()
+        .take(NEIGHBOURS)
+        .map(|r| {
+            let owner = r.owner.map_or("nobody".into(), |id| format!("<@{id}>"));
+            match r.perimeter.distance(&p) {
+                0 if r.perimeter.intersects(&p) => format!("{r} of {owner}, overlapping"),
+                d => format!("{r} of {owner}, {d}m away"),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let others = plots
+        .iter()
+        .map(|r| r.perimeter.clone())
+        .collect::<Vec<_>>();
+    let map = render_map(&p, &others, zones);
+
+    CreateEmbed::default()
+        .title("New plot")
+        .color(if collides { Color::RED } else { Color::BLURPLE })
+        .description(format!(
+            "Move the edges with the buttons below until your plot fits, \
+             then confirm it.\n```\n{map}```██ your plot  ▓▓ other plots  ░░ zones · north is up"
+        ))
+        .field(
+            "Size",
+            format!("{} × {} ({}m²)", p.1 .0 - p.0 .0, p.1 .1 - p.0 .1, p.size()),
+            true,
+        )
+        .field("World", world, true)
+        .field(
+            "Perimeter",
+            format!("`{}, {}` → `{}, {}`", p.0 .0, p.0 .1, p.1 .0, p.1 .1),
+            true,
+        )
+        .field(
+            "Nearest plots",
+            match neighbours.is_empty() {
+                true => "*none*".to_owned(),
+                false => neighbours.join("\n"),
+            },
+            false,
+        )
+        .to_owned()
+}
+
+fn buttons<'a>(c: &'a mut CreateComponents, prefix: &str) -> &'a mut CreateComponents {
+    for side in Side::ALL {
+        c.create_action_row(|row| {
+            for delta in STEPS {
+                row.create_button(|btn| {
+                    btn.custom_id(format!("{prefix}:{}:{delta}", side.key()))
+                        .style(ButtonStyle::Secondary)
+                        .label(format!("{side:?} {delta:+}"))
+                });
+            }
+            row
+        });
+    }
+
+    c.create_action_row(|row| {
+        row.create_button(|btn| {
+            btn.custom_id(format!("{prefix}:confirm"))
+                .style(ButtonStyle::Success)
+                .label("Create Plot")
+        })
+        .create_button(|btn| {
+            btn.custom_id(format!("{prefix}:cancel"))
+                .style(ButtonStyle::Secondary)
+                .label("Cancel")
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_ids() {
+        assert_eq!(parse_custom_id("abc", "abc:confirm"), Some(Action::Confirm));
+        assert_eq!(parse_custom_id("abc", "abc:cancel"), Some(Action::Cancel));
+        assert_eq!(
+            parse_custom_id("abc", "abc:w:-10"),
+            Some(Action::Nudge(Side::West, -10))
+        );
+        assert_eq!(parse_custom_id("abc", "abc:x:1"), None);
+        assert_eq!(parse_custom_id("abc", "other:confirm"), None);
+    }
+
+    #[test]
+    fn nudge_edges() {
+        let p = Perimeter(Point(10, 10), Point(0, 0));
+
+        assert_eq!(
+            nudge(&p, Side::North, 10),
+            Perimeter(Point(0, -10), Point(10, 10))
+        );
+        assert_eq!(
+            nudge(&p, Side::East, -1),
+            Perimeter(Point(0, 0), Point(9, 10))
+        );
+        assert_eq!(
+            nudge(&p, Side::West, 1),
+            Perimeter(Point(-1, 0), Point(10, 10))
+        );
+        // An edge can not be moved past the opposite one.
+        assert_eq!(
+            nudge(&p, Side::South, -10),
+            Perimeter(Point(0, 0), Point(10, 10))
+        );
+    }
+
+    #[test]
+    fn map() {
+        let p = Perimeter(Point(0, 0), Point(12, 12));
+        let other = Perimeter(Point(-12, -12), Point(-2, -2));
+        let map = render_map(&p, &[other], &[]);
+
+        let rows = map.lines().collect::<Vec<_>>();
+        assert_eq!(rows.len(), MAP_CELLS as usize);
+        assert!(rows
+            .iter()
+            .all(|r| r.chars().count() == 2 * MAP_CELLS as usize));
+        // The plot is centred and the other plot lies to the north-west.
+        assert!(rows[6].contains("██"));
+        assert!(rows[0].starts_with("▓▓"));
+        assert!(rows[11].ends_with("··"));
+    }
+}